@@ -0,0 +1,100 @@
+//! `count`の処理速度を、行や文字ごとに`String`を割り当てていた以前の実装と比較する。
+//!
+//! `cargo bench`で実行する。
+use std::collections::HashMap;
+use std::hint::black_box;
+use std::io::{BufRead, Cursor};
+use std::time::{Duration, Instant};
+
+use kuroyasu_bicycle_book_wordcount::{count, CountOption};
+use regex::Regex;
+
+/// 測定に使用する入力の大きさ（バイト）
+const INPUT_SIZE: usize = 8 << 20;
+/// 測定を繰り返す回数。最も速かった時間を結果とする。
+const ITERATIONS: usize = 5;
+
+/// 以前の`count`と同じく、行、文字及び単語ごとに`String`を割り当てて数える。
+fn count_baseline(input: impl BufRead, option: CountOption) -> HashMap<String, usize> {
+    let re = Regex::new(r"\w+").unwrap();
+    let mut freqs = HashMap::new();
+
+    for line in input.lines() {
+        let line = line.unwrap();
+        match option {
+            CountOption::Char => {
+                for c in line.chars() {
+                    *freqs.entry(c.to_string()).or_insert(0) += 1;
+                }
+            }
+            CountOption::Word => {
+                for m in re.find_iter(&line) {
+                    let word = m.as_str().to_string();
+                    *freqs.entry(word).or_insert(0) += 1;
+                }
+            }
+            CountOption::Line => *freqs.entry(line.to_string()).or_insert(0) += 1,
+            _ => unreachable!(),
+        }
+    }
+
+    freqs
+}
+
+/// 英語と日本語の文が混在する、約`size`バイトの入力を作成する。
+fn input(size: usize) -> String {
+    let sentences = [
+        "The quick brown fox jumps over the lazy dog.",
+        "Rust is a multi-paradigm, general-purpose programming language.",
+        "吾輩は猫である。名前はまだ無い。",
+        "どこで生れたかとんと見当がつかぬ。",
+        "error: connection reset by peer",
+    ];
+    let mut text = String::with_capacity(size + 128);
+    let mut i = 0;
+    while text.len() < size {
+        text.push_str(sentences[i % sentences.len()]);
+        text.push('\n');
+        i += 1;
+    }
+    text
+}
+
+fn measure(f: impl Fn() -> HashMap<String, usize>) -> Duration {
+    (0..ITERATIONS)
+        .map(|_| {
+            let start = Instant::now();
+            black_box(f());
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn main() {
+    let text = input(INPUT_SIZE);
+    let mb = text.len() as f64 / (1 << 20) as f64;
+    println!(
+        "{:<6} {:>14} {:>14} {:>8}",
+        "mode", "baseline MB/s", "count MB/s", "speedup"
+    );
+    for (name, option) in [
+        ("char", CountOption::Char),
+        ("word", CountOption::Word),
+        ("line", CountOption::Line),
+    ] {
+        assert_eq!(
+            count(Cursor::new(&text), option),
+            count_baseline(Cursor::new(&text), option)
+        );
+        let baseline = measure(|| count_baseline(Cursor::new(&text), option));
+        let current = measure(|| count(Cursor::new(&text), option));
+        println!(
+            "{:<6} {:>14.1} {:>14.1} {:>7.2}x",
+            name,
+            mb / baseline.as_secs_f64(),
+            mb / current.as_secs_f64(),
+            baseline.as_secs_f64() / current.as_secs_f64()
+        );
+    }
+}
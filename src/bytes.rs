@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::io::{self, Read};
+
+use crate::CountError;
+
+/// 一度に読み込むバイト数
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// `input`を文字列としてデコードせずに、バイトの出現頻度を数える。
+///
+/// 戻り値の`i`番目の要素が、バイト`i`の出現頻度である。
+///
+/// # Panics
+///
+/// 入力の読み込みに失敗した場合は、パニックを起こす。
+/// パニックを避けたい場合は、[`try_count_bytes`](fn.try_count_bytes.html)を使用すること。
+///
+/// # Examples
+///
+/// ```
+/// use kuroyasu_bicycle_book_wordcount::count_bytes;
+///
+/// let histogram = count_bytes(&b"\x7fELF\x00\x00"[..]);
+/// assert_eq!(histogram[0x00], 2);
+/// assert_eq!(histogram[b'E' as usize], 1);
+/// ```
+pub fn count_bytes(input: impl Read) -> [usize; 256] {
+    try_count_bytes(input).unwrap_or_else(|e| panic!("{}", e))
+}
+
+/// [`count_bytes`](fn.count_bytes.html)と同様に出現頻度を数えるが、パニックを起こす代わりにエラーを返す。
+///
+/// # Errors
+///
+/// 入力の読み込みに失敗した場合は、[`CountError::Io`](enum.CountError.html#variant.Io)を返す。
+pub fn try_count_bytes(input: impl Read) -> Result<[usize; 256], CountError> {
+    let mut histogram = [0; 256];
+    for_each_chunk(input, |chunk| {
+        chunk.iter().for_each(|&b| histogram[usize::from(b)] += 1);
+    })?;
+    Ok(histogram)
+}
+
+/// `input`を文字列としてデコードせずに、連続する`n`バイト（バイトn-gram）の出現頻度を数える。
+///
+/// `n`が`0`の場合は、何も数えない。
+///
+/// # Panics
+///
+/// 入力の読み込みに失敗した場合は、パニックを起こす。
+/// パニックを避けたい場合は、[`try_count_byte_ngrams`](fn.try_count_byte_ngrams.html)を使用すること。
+///
+/// # Examples
+///
+/// ```
+/// use kuroyasu_bicycle_book_wordcount::count_byte_ngrams;
+///
+/// let freqs = count_byte_ngrams(&b"\xca\xfe\xca\xfe"[..], 2);
+/// assert_eq!(freqs[&b"\xca\xfe"[..]], 2);
+/// assert_eq!(freqs[&b"\xfe\xca"[..]], 1);
+/// ```
+pub fn count_byte_ngrams(input: impl Read, n: usize) -> HashMap<Vec<u8>, usize> {
+    try_count_byte_ngrams(input, n).unwrap_or_else(|e| panic!("{}", e))
+}
+
+/// [`count_byte_ngrams`](fn.count_byte_ngrams.html)と同様に出現頻度を数えるが、パニックを起こす代わりに
+/// エラーを返す。
+///
+/// # Errors
+///
+/// 入力の読み込みに失敗した場合は、[`CountError::Io`](enum.CountError.html#variant.Io)を返す。
+pub fn try_count_byte_ngrams(
+    input: impl Read,
+    n: usize,
+) -> Result<HashMap<Vec<u8>, usize>, CountError> {
+    let mut freqs: HashMap<Vec<u8>, usize> = HashMap::new();
+    if n == 0 {
+        return Ok(freqs);
+    }
+    // 前の塊の末尾`n - 1`バイトを次の塊の先頭に連結して、塊をまたぐn-gramも数える。
+    let mut window = Vec::new();
+    for_each_chunk(input, |chunk| {
+        window.extend_from_slice(chunk);
+        for ngram in window.windows(n) {
+            match freqs.get_mut(ngram) {
+                Some(count) => *count += 1,
+                None => {
+                    freqs.insert(ngram.to_vec(), 1);
+                }
+            }
+        }
+        let keep = window.len().min(n - 1);
+        window.drain(..window.len() - keep);
+    })?;
+    Ok(freqs)
+}
+
+/// バイト列を、区切りのない小文字の16進数の文字列に変換する。
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    let mut s = String::with_capacity(bytes.len() * 2);
+    for b in bytes {
+        write!(s, "{:02x}", b).unwrap();
+    }
+    s
+}
+
+/// `input`を塊ごとに読み込み、`f`に渡す。
+///
+/// エラーの行番号は、読み込み済みの`\n`の数から求める。
+fn for_each_chunk(mut input: impl Read, mut f: impl FnMut(&[u8])) -> Result<(), CountError> {
+    let mut buf = vec![0; CHUNK_SIZE];
+    let mut line = 1;
+    let mut offset = 0;
+    loop {
+        let len = match input.read(&mut buf) {
+            Ok(0) => return Ok(()),
+            Ok(len) => len,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(source) => {
+                return Err(CountError::Io {
+                    line,
+                    offset,
+                    source,
+                })
+            }
+        };
+        let chunk = &buf[..len];
+        line += chunk.iter().filter(|&&b| b == b'\n').count();
+        offset += len;
+        f(chunk);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 1バイトずつしか読み込めない入力
+    struct OneByte<'a>(&'a [u8]);
+
+    impl Read for OneByte<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match self.0.split_first() {
+                Some((&b, rest)) if !buf.is_empty() => {
+                    buf[0] = b;
+                    self.0 = rest;
+                    Ok(1)
+                }
+                _ => Ok(0),
+            }
+        }
+    }
+
+    #[test]
+    fn ngrams_across_chunks() {
+        let input = b"abcabca";
+        let freqs = try_count_byte_ngrams(OneByte(input), 3).unwrap();
+        assert_eq!(freqs, try_count_byte_ngrams(&input[..], 3).unwrap());
+        assert_eq!(freqs[&b"abc"[..]], 2);
+        assert_eq!(freqs.values().sum::<usize>(), 5);
+        assert!(try_count_byte_ngrams(&input[..], 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn hex_keys() {
+        assert_eq!(hex(b"\x00\x7f\xff"), "007fff");
+    }
+}
@@ -0,0 +1,190 @@
+//! 異なるトークンの数（カーディナリティ）を、HyperLogLogで推定する機能を提供する。
+//!
+//! 出現頻度は不要で、異なる単語や行が何種類あるかだけを知りたい場合は、全てのトークンを
+//! `HashMap`に記録する代わりに、`2^precision`バイトのレジスターだけで推定できる。
+
+/// 指定できる精度の最小値
+pub const MIN_PRECISION: u8 = 4;
+/// 指定できる精度の最大値
+pub const MAX_PRECISION: u8 = 18;
+/// [`HyperLogLog::default`](struct.HyperLogLog.html)で使用する精度
+pub const DEFAULT_PRECISION: u8 = 14;
+
+/// 異なるトークンの数を推定するHyperLogLog
+///
+/// トークンのハッシュ値はこのクレートで実装しているため、プラットフォームやRustのバージョンに
+/// よらず同じになる。同じ精度のスケッチは、[`merge`](#method.merge)で併合できる。
+///
+/// # Examples
+///
+/// ```
+/// use kuroyasu_bicycle_book_wordcount::cardinality::HyperLogLog;
+///
+/// let mut a = HyperLogLog::new(12);
+/// let mut b = HyperLogLog::new(12);
+/// (0..5000).for_each(|i| a.add(&i.to_string()));
+/// (2500..10000).for_each(|i| b.add(&i.to_string()));
+/// a.merge(&b);
+///
+/// let error = (a.estimate() - 10000.0).abs() / 10000.0;
+/// assert!(error < 3.0 * a.standard_error());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HyperLogLog {
+    precision: u8,
+    registers: Vec<u8>,
+}
+
+impl HyperLogLog {
+    /// `2^precision`個のレジスターを使用するスケッチを作成する。
+    ///
+    /// 精度を1上げると、使用するメモリーは2倍になり、標準誤差は約`1/√2`倍になる。
+    ///
+    /// # Panics
+    ///
+    /// `precision`が[`MIN_PRECISION`](constant.MIN_PRECISION.html)以上
+    /// [`MAX_PRECISION`](constant.MAX_PRECISION.html)以下でない場合は、パニックを起こす。
+    pub fn new(precision: u8) -> Self {
+        assert!(
+            (MIN_PRECISION..=MAX_PRECISION).contains(&precision),
+            "精度は{}以上{}以下で指定してください: {}",
+            MIN_PRECISION,
+            MAX_PRECISION,
+            precision
+        );
+        HyperLogLog {
+            precision,
+            registers: vec![0; 1 << precision],
+        }
+    }
+
+    /// スケッチの精度を返す。
+    pub fn precision(&self) -> u8 {
+        self.precision
+    }
+
+    /// `token`を記録する。
+    pub fn add(&mut self, token: &str) {
+        let hash = hash(token.as_bytes());
+        let p = self.precision;
+        let index = (hash >> (64 - p)) as usize;
+        // 残りのビットの先頭から数えた、最初に1が現れる位置
+        let rank = ((hash << p) | (1 << (p - 1))).leading_zeros() as u8 + 1;
+        if self.registers[index] < rank {
+            self.registers[index] = rank;
+        }
+    }
+
+    /// `other`に記録されたトークンを併合する。
+    ///
+    /// 併合したスケッチは、両方のトークンを1つのスケッチに記録した場合と同じになる。
+    ///
+    /// # Panics
+    ///
+    /// `other`の精度が異なる場合は、パニックを起こす。
+    pub fn merge(&mut self, other: &HyperLogLog) {
+        assert_eq!(
+            self.precision, other.precision,
+            "精度が異なるスケッチは併合できません"
+        );
+        for (r, &o) in self.registers.iter_mut().zip(&other.registers) {
+            if *r < o {
+                *r = o;
+            }
+        }
+    }
+
+    /// 記録された異なるトークンの数の推定値を返す。
+    pub fn estimate(&self) -> f64 {
+        let m = self.registers.len() as f64;
+        let alpha = match self.registers.len() {
+            16 => 0.673,
+            32 => 0.697,
+            64 => 0.709,
+            _ => 0.7213 / (1.0 + 1.079 / m),
+        };
+        let sum: f64 = self
+            .registers
+            .iter()
+            .map(|&r| 2f64.powi(-i32::from(r)))
+            .sum();
+        let estimate = alpha * m * m / sum;
+
+        // 推定値が小さい場合は、値が0のレジスターの数から線形計数法で推定する。
+        let zeros = self.registers.iter().filter(|&&r| r == 0).count();
+        if estimate <= 2.5 * m && zeros > 0 {
+            m * (m / zeros as f64).ln()
+        } else {
+            estimate
+        }
+    }
+
+    /// 推定値の相対標準誤差（`1.04/√m`）を返す。`m`はレジスターの数。
+    pub fn standard_error(&self) -> f64 {
+        1.04 / (self.registers.len() as f64).sqrt()
+    }
+}
+
+/// 精度が[`DEFAULT_PRECISION`](constant.DEFAULT_PRECISION.html)のスケッチを作成する。
+impl Default for HyperLogLog {
+    fn default() -> Self {
+        HyperLogLog::new(DEFAULT_PRECISION)
+    }
+}
+
+/// FNV-1aで求めたハッシュ値を、MurmurHash3の最終処理で攪拌した64ビットのハッシュ値を返す。
+fn hash(bytes: &[u8]) -> u64 {
+    let mut h: u64 = 0xcbf2_9ce4_8422_2325;
+    for &b in bytes {
+        h ^= u64::from(b);
+        h = h.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    h ^ (h >> 33)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn estimate_within_error() {
+        for n in [0, 10, 1000, 100_000] {
+            let mut hll = HyperLogLog::default();
+            for i in 0..n {
+                // 同じトークンを2回記録しても、数は変わらない。
+                hll.add(&format!("token{}", i));
+                hll.add(&format!("token{}", i));
+            }
+            let error = (hll.estimate() - n as f64).abs() / (n as f64).max(1.0);
+            assert!(error < 3.0 * hll.standard_error(), "n = {}", n);
+        }
+    }
+
+    #[test]
+    fn merge_equals_union() {
+        let mut a = HyperLogLog::new(8);
+        let mut b = HyperLogLog::new(8);
+        let mut union = HyperLogLog::new(8);
+        for i in 0..300 {
+            let token = i.to_string();
+            if i % 2 == 0 {
+                a.add(&token);
+            } else {
+                b.add(&token);
+            }
+            union.add(&token);
+        }
+        a.merge(&b);
+        assert_eq!(a, union);
+    }
+
+    #[test]
+    #[should_panic]
+    fn merge_different_precision() {
+        HyperLogLog::new(8).merge(&HyperLogLog::new(9));
+    }
+}
@@ -0,0 +1,227 @@
+//! 隣り合うトークンの組（バイグラム）の出現頻度から、連語（コロケーション）を求める機能を提供する。
+//!
+//! バイグラムは、[`count_collocations`](../fn.count_collocations.html)などで1行ずつ数え、
+//! 行をまたぐ組は数えない。各組の結びつきの強さは、バイグラムの2×2分割表から求める。
+use std::collections::HashMap;
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+/// 連語を並べる基準（結びつきの強さの尺度）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Association {
+    /// 自己相互情報量（PMI）。出現頻度が低い組ほど大きくなりやすい。
+    Pmi,
+    /// t値。出現頻度が高い組ほど大きくなりやすい。
+    TScore,
+    /// 対数尤度比（G²）
+    #[default]
+    LogLikelihood,
+}
+
+/// 隣り合う2つのトークンと、その結びつきの強さ
+///
+/// `log_likelihood`は、組の出現頻度が期待値より低い場合は負の値とする。
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Collocation<'a> {
+    /// 前のトークン
+    pub first: &'a str,
+    /// 後ろのトークン
+    pub second: &'a str,
+    /// 組の出現頻度
+    pub count: usize,
+    /// 自己相互情報量（ビット）
+    pub pmi: f64,
+    /// t値
+    pub t_score: f64,
+    /// 対数尤度比（G²）
+    pub log_likelihood: f64,
+}
+
+impl Collocation<'_> {
+    /// `association`で指定された尺度の値を返す。
+    pub fn score(&self, association: Association) -> f64 {
+        match association {
+            Association::Pmi => self.pmi,
+            Association::TScore => self.t_score,
+            Association::LogLikelihood => self.log_likelihood,
+        }
+    }
+}
+
+/// 隣り合うトークンの組の出現頻度
+///
+/// # Examples
+///
+/// ```
+/// use kuroyasu_bicycle_book_wordcount::collocation::{Association, Collocations};
+///
+/// let mut collocations = Collocations::new();
+/// for line in ["machine learning is fun", "machine learning is hard", "learning is fun"] {
+///     let tokens: Vec<_> = line.split(' ').collect();
+///     for pair in tokens.windows(2) {
+///         collocations.add(pair[0], pair[1]);
+///     }
+/// }
+/// assert_eq!(collocations.count("machine", "learning"), 2);
+///
+/// let ranked = collocations.rank(Association::Pmi, 2);
+/// assert_eq!((ranked[0].first, ranked[0].second), ("machine", "learning"));
+/// assert_eq!(ranked.len(), 3);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Collocations {
+    /// 前のトークンから、後ろのトークンごとの出現頻度への対応
+    bigrams: HashMap<String, HashMap<String, usize>>,
+    /// 組の前のトークンとしての出現頻度
+    firsts: HashMap<String, usize>,
+    /// 組の後ろのトークンとしての出現頻度
+    seconds: HashMap<String, usize>,
+    total: usize,
+}
+
+impl Collocations {
+    /// 組のない出現頻度を作成する。
+    pub fn new() -> Self {
+        Collocations::default()
+    }
+
+    /// `first`の直後に`second`が出現したことを数える。
+    pub fn add(&mut self, first: &str, second: &str) {
+        match self.bigrams.get_mut(first) {
+            Some(seconds) => crate::increment(seconds, second),
+            None => {
+                let mut seconds = HashMap::new();
+                seconds.insert(second.to_string(), 1);
+                self.bigrams.insert(first.to_string(), seconds);
+            }
+        }
+        crate::increment(&mut self.firsts, first);
+        crate::increment(&mut self.seconds, second);
+        self.total += 1;
+    }
+
+    /// `first`の直後に`second`が出現した回数を返す。
+    pub fn count(&self, first: &str, second: &str) -> usize {
+        self.bigrams
+            .get(first)
+            .and_then(|seconds| seconds.get(second))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// 数えた全ての組の出現頻度の合計を返す。
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// 異なる組の数を返す。
+    pub fn len(&self) -> usize {
+        self.bigrams.values().map(HashMap::len).sum()
+    }
+
+    /// 組がなければ`true`を返す。
+    pub fn is_empty(&self) -> bool {
+        self.total == 0
+    }
+
+    /// 出現頻度が`min_count`以上の組を、`association`の値が大きい順に返す。
+    ///
+    /// 値が同じ組は、前のトークン、後ろのトークンの辞書順に並べる。
+    pub fn rank(&self, association: Association, min_count: usize) -> Vec<Collocation<'_>> {
+        let n = self.total as f64;
+        let mut ranked: Vec<_> = self
+            .bigrams
+            .iter()
+            .flat_map(|(first, seconds)| {
+                seconds
+                    .iter()
+                    .map(move |(second, &count)| (first.as_str(), second.as_str(), count))
+            })
+            .filter(|&(_, _, count)| count >= min_count)
+            .map(|(first, second, count)| {
+                let o11 = count as f64;
+                let row = self.firsts[first] as f64;
+                let column = self.seconds[second] as f64;
+                let expected = row * column / n;
+                Collocation {
+                    first,
+                    second,
+                    count,
+                    pmi: (o11 / expected).log2(),
+                    t_score: (o11 - expected) / o11.sqrt(),
+                    log_likelihood: log_likelihood(o11, row, column, n),
+                }
+            })
+            .collect();
+        ranked.sort_by(|a, b| {
+            b.score(association)
+                .total_cmp(&a.score(association))
+                .then_with(|| (a.first, a.second).cmp(&(b.first, b.second)))
+        });
+        ranked
+    }
+}
+
+/// 組の出現頻度`o11`、前のトークンの出現頻度`row`、後ろのトークンの出現頻度`column`、組の総数`n`の
+/// 2×2分割表から、G²を求める。
+fn log_likelihood(o11: f64, row: f64, column: f64, n: f64) -> f64 {
+    let observed = [o11, row - o11, column - o11, n - row - column + o11];
+    let expected = [
+        row * column / n,
+        row * (n - column) / n,
+        (n - row) * column / n,
+        (n - row) * (n - column) / n,
+    ];
+    let g2: f64 = observed
+        .iter()
+        .zip(&expected)
+        .filter(|&(&o, _)| o > 0.0)
+        .map(|(&o, &e)| o * (o / e).ln())
+        .sum::<f64>()
+        * 2.0;
+    if o11 < expected[0] {
+        -g2
+    } else {
+        g2
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-6,
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn known_values() {
+        // 組の総数10のうち、(a, b)が3回、aが前に4回、bが後ろに5回出現した場合
+        let mut collocations = Collocations::new();
+        for (first, second, times) in [("a", "b", 3), ("a", "c", 1), ("d", "b", 2), ("d", "c", 4)] {
+            (0..times).for_each(|_| collocations.add(first, second));
+        }
+        assert_eq!(collocations.total(), 10);
+        assert_eq!(collocations.len(), 4);
+
+        let ranked = collocations.rank(Association::LogLikelihood, 3);
+        assert_eq!(ranked.len(), 2);
+        let ab = ranked.iter().find(|c| c.first == "a").unwrap();
+        assert_close(ab.pmi, (3.0f64 / 2.0).log2());
+        assert_close(ab.t_score, 1.0 / 3.0f64.sqrt());
+        assert_close(ab.log_likelihood, 1.726_092);
+    }
+
+    #[test]
+    fn negative_association() {
+        assert!(log_likelihood(1.0, 5.0, 5.0, 10.0) < 0.0);
+        assert_eq!(log_likelihood(5.0, 5.0, 5.0, 5.0), 0.0);
+    }
+}
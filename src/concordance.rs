@@ -0,0 +1,243 @@
+//! 指定されたトークンの出現箇所を、前後の文脈とともに取り出す（KWIC）機能を提供する。
+//!
+//! トークンの位置は、トークナイザーが返した文字列が行のどこを指しているかで求める。
+//! このため、[`NormalizedTokenizer`](../tokenizer/struct.NormalizedTokenizer.html)のように
+//! 行にない文字列を返すトークナイザーでは、その文字列を行の中から探す。見つからないトークンは
+//! 取り出さないため、正規化した結果で比較したい場合は、正規化する前のトークナイザーと
+//! [`find_by`](fn.find_by.html)を使用すること。
+use std::io::BufRead;
+use std::ops::Range;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::tokenizer::Tokenizer;
+use crate::CountError;
+
+/// 出現箇所の前後に含める文脈の長さ
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Context {
+    /// 前後それぞれ指定された数の文字
+    Chars(usize),
+    /// 前後それぞれ指定された数のトークン。最初または最後のトークンまでの場合は、行頭または行末まで含める。
+    Tokens(usize),
+}
+
+/// トークンの出現箇所
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Occurrence {
+    /// 行番号（1から数える）
+    pub line: usize,
+    /// 行頭からのバイト数
+    pub offset: usize,
+    /// 前の文脈
+    pub left: String,
+    /// 出現したトークン（行中の文字列）
+    pub keyword: String,
+    /// 後ろの文脈
+    pub right: String,
+}
+
+/// `input`から1行ずつ`tokenizer`でトークンを取り出し、`keyword`と等しいトークンの出現箇所を
+/// `context`で指定された文脈とともに返す。
+///
+/// 文脈は同じ行の中だけから取り出す。
+///
+/// # Panics
+///
+/// 入力の読み込みに失敗した場合、または入力がUTF-8文字列でない場合は、パニックを起こす。
+/// パニックを避けたい場合は、[`try_find`](fn.try_find.html)を使用すること。
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use kuroyasu_bicycle_book_wordcount::concordance::{find, Context};
+/// use kuroyasu_bicycle_book_wordcount::CountOption;
+///
+/// let input = Cursor::new("the cat sat on the mat\nno cats here\na cat");
+/// let occurrences = find(input, CountOption::Word.tokenizer(), "cat", Context::Tokens(2));
+/// assert_eq!(occurrences.len(), 2);
+/// assert_eq!(occurrences[0].line, 1);
+/// assert_eq!(occurrences[0].left, "the ");
+/// assert_eq!(occurrences[0].right, " sat on");
+/// assert_eq!((occurrences[1].line, occurrences[1].offset), (3, 2));
+/// ```
+pub fn find(
+    input: impl BufRead,
+    tokenizer: impl Tokenizer,
+    keyword: &str,
+    context: Context,
+) -> Vec<Occurrence> {
+    try_find(input, tokenizer, keyword, context).unwrap_or_else(|e| panic!("{}", e))
+}
+
+/// [`find`](fn.find.html)と同様に取り出すが、入力に問題がある場合はパニックを起こす代わりにエラーを返す。
+///
+/// # Errors
+///
+/// [`try_count`](../fn.try_count.html)と同じ条件でエラーを返す。
+pub fn try_find(
+    input: impl BufRead,
+    tokenizer: impl Tokenizer,
+    keyword: &str,
+    context: Context,
+) -> Result<Vec<Occurrence>, CountError> {
+    try_find_by(input, tokenizer, |token| token == keyword, context)
+}
+
+/// [`find`](fn.find.html)と同様に取り出すが、`is_keyword`が`true`を返したトークンの出現箇所を返す。
+///
+/// 大文字と小文字を区別しない場合など、トークンを変換してから比較する場合に使用する。
+///
+/// # Panics
+///
+/// [`find`](fn.find.html)と同じ条件でパニックを起こす。
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use kuroyasu_bicycle_book_wordcount::concordance::{find_by, Context};
+/// use kuroyasu_bicycle_book_wordcount::tokenizer::WordTokenizer;
+///
+/// let input = Cursor::new("Rust and rust");
+/// let occurrences = find_by(input, WordTokenizer, |t| t.eq_ignore_ascii_case("rust"), Context::Chars(3));
+/// assert_eq!(occurrences[0].keyword, "Rust");
+/// assert_eq!(occurrences[1].left, "nd ");
+/// ```
+pub fn find_by(
+    input: impl BufRead,
+    tokenizer: impl Tokenizer,
+    is_keyword: impl FnMut(&str) -> bool,
+    context: Context,
+) -> Vec<Occurrence> {
+    try_find_by(input, tokenizer, is_keyword, context).unwrap_or_else(|e| panic!("{}", e))
+}
+
+/// [`find_by`](fn.find_by.html)と同様に取り出すが、入力に問題がある場合はパニックを起こす代わりに
+/// エラーを返す。
+///
+/// # Errors
+///
+/// [`try_count`](../fn.try_count.html)と同じ条件でエラーを返す。
+pub fn try_find_by(
+    input: impl BufRead,
+    mut tokenizer: impl Tokenizer,
+    mut is_keyword: impl FnMut(&str) -> bool,
+    context: Context,
+) -> Result<Vec<Occurrence>, CountError> {
+    let mut occurrences = Vec::new();
+    let mut line_no = 0;
+    // 行中のトークンの位置と、探しているトークンかどうか
+    let mut spans: Vec<(Range<usize>, bool)> = Vec::new();
+    crate::for_each_line(input, |line| {
+        line_no += 1;
+        spans.clear();
+        let mut from = 0;
+        tokenizer.tokenize(line, &mut |token| {
+            if let Some(span) = locate(line, token, from) {
+                // n-gramのように重なり合うトークンも見つけられるように、次は1文字後ろから探す。
+                from = span.start + line[span.start..].chars().next().map_or(0, char::len_utf8);
+                spans.push((span, is_keyword(token)));
+            }
+        });
+        for (i, (span, _)) in spans.iter().enumerate().filter(|(_, s)| s.1) {
+            let (left, right) = match context {
+                Context::Chars(n) => {
+                    let before = &line[..span.start];
+                    let start = before
+                        .char_indices()
+                        .rev()
+                        .nth(n.saturating_sub(1))
+                        .map_or(0, |(i, _)| i);
+                    let after = &line[span.end..];
+                    let end = after.char_indices().nth(n).map_or(after.len(), |(i, _)| i);
+                    (if n == 0 { "" } else { &before[start..] }, &after[..end])
+                }
+                Context::Tokens(n) => {
+                    let start = if i >= n { spans[i - n].0.start } else { 0 };
+                    let end = spans.get(i + n).map_or(line.len(), |s| s.0.end);
+                    (
+                        &line[start.min(span.start)..span.start],
+                        &line[span.end..end.max(span.end)],
+                    )
+                }
+            };
+            occurrences.push(Occurrence {
+                line: line_no,
+                offset: span.start,
+                left: left.to_string(),
+                keyword: line[span.clone()].to_string(),
+                right: right.to_string(),
+            });
+        }
+    })?;
+    // 入力の終わりに返されるトークンは、行に対応しないため取り出さない。
+    tokenizer.finish(&mut |_| {});
+    Ok(occurrences)
+}
+
+/// `token`の`line`中の位置を返す。
+///
+/// `token`が`line`の一部であればその位置を、そうでなければ`from`以降で最初に現れる位置を返す。
+pub(crate) fn locate(line: &str, token: &str, from: usize) -> Option<Range<usize>> {
+    let base = line.as_ptr() as usize;
+    let start = token.as_ptr() as usize;
+    if base <= start && start + token.len() <= base + line.len() {
+        let offset = start - base;
+        return Some(offset..offset + token.len());
+    }
+    line.get(from..)?
+        .find(token)
+        .map(|i| from + i..from + i + token.len())
+}
+
+/// 等幅フォントで表示した場合の文字列の幅を返す。
+///
+/// 東アジアの全角文字と絵文字を2、それ以外の文字を1として数える。結合文字なども1として数える。
+pub fn display_width(s: &str) -> usize {
+    s.chars().map(|c| if is_wide(c) { 2 } else { 1 }).sum()
+}
+
+/// `c`が全角で表示される文字であれば`true`を返す。
+fn is_wide(c: char) -> bool {
+    matches!(
+        c,
+        '\u{1100}'..='\u{115F}'
+            | '\u{2E80}'..='\u{303E}'
+            | '\u{3041}'..='\u{33FF}'
+            | '\u{3400}'..='\u{4DBF}'
+            | '\u{4E00}'..='\u{9FFF}'
+            | '\u{A000}'..='\u{A4CF}'
+            | '\u{AC00}'..='\u{D7A3}'
+            | '\u{F900}'..='\u{FAFF}'
+            | '\u{FE30}'..='\u{FE4F}'
+            | '\u{FF00}'..='\u{FF60}'
+            | '\u{FFE0}'..='\u{FFE6}'
+            | '\u{1F300}'..='\u{1F64F}'
+            | '\u{1F900}'..='\u{1F9FF}'
+            | '\u{20000}'..='\u{3FFFD}'
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locate_slices_and_copies() {
+        let line = "ab ab";
+        assert_eq!(locate(line, &line[3..], 0), Some(3..5));
+        assert_eq!(locate(line, "ab", 1), Some(3..5));
+        assert_eq!(locate(line, "AB", 0), None);
+    }
+
+    #[test]
+    fn width_of_mixed_text() {
+        assert_eq!(display_width("abc"), 3);
+        assert_eq!(display_width("出力ｱ"), 5);
+        assert_eq!(display_width("ＡＢ"), 4);
+    }
+}
@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::tokenizer::Tokenizer;
+use crate::CountOption;
+
+/// 少しずつ届く文字列の出現頻度を数えるカウンター
+///
+/// [`count`](fn.count.html)は入力全体を読み込むが、`Counter`は[`feed`](#method.feed)で
+/// 渡された文字列を順に数える。文字列は行の途中で区切られていてもよい。
+///
+/// # Examples
+///
+/// ```
+/// use kuroyasu_bicycle_book_wordcount::{CountOption, Counter};
+///
+/// let mut counter = Counter::new(CountOption::Word);
+/// counter.feed("aa b");
+/// counter.feed("b\ncc a");
+/// // 改行で終わっていない`cc a`は、まだ数えられていない。
+/// assert_eq!(counter.frequencies()["bb"], 1);
+/// assert!(!counter.frequencies().contains_key("cc"));
+///
+/// let mut other = Counter::new(CountOption::Word);
+/// other.feed("bb\n");
+/// counter.merge(other);
+///
+/// let freqs = counter.finish();
+/// assert_eq!(freqs["aa"], 1);
+/// assert_eq!(freqs["bb"], 2);
+/// assert_eq!(freqs["cc"], 1);
+/// assert_eq!(freqs["a"], 1);
+/// ```
+pub struct Counter {
+    tokenizer: Box<dyn Tokenizer + Send>,
+    freqs: HashMap<String, usize>,
+    /// 改行で終わっていない、最後の行の文字列
+    pending: String,
+}
+
+impl Counter {
+    /// `option`で指定された対象を数えるカウンターを作成する。
+    ///
+    /// # Panics
+    ///
+    /// `option`が[`Byte`](enum.CountOption.html#variant.Byte)または
+    /// [`ByteNgram`](enum.CountOption.html#variant.ByteNgram)の場合は、パニックを起こす。
+    /// `Counter`は文字列を数えるため、[`count`](fn.count.html)と同じように改行を含む入力の
+    /// 全てのバイトを数えることはできない。
+    pub fn new(option: CountOption) -> Self {
+        assert!(
+            !matches!(option, CountOption::Byte | CountOption::ByteNgram { .. }),
+            "Counter cannot count bytes; use count instead"
+        );
+        Counter::with_tokenizer(option.tokenizer())
+    }
+
+    /// `tokenizer`が取り出したトークンを数えるカウンターを作成する。
+    pub fn with_tokenizer(tokenizer: impl Tokenizer + Send + 'static) -> Self {
+        Counter {
+            tokenizer: Box::new(tokenizer),
+            freqs: HashMap::new(),
+            pending: String::new(),
+        }
+    }
+
+    /// `chunk`を入力の続きとして数える。
+    ///
+    /// `chunk`の最後の行が改行で終わっていない場合、その行は次に渡された文字列と連結してから数える。
+    /// 行末の`\n`または`\r\n`は、[`count`](fn.count.html)と同様に取り除く。
+    pub fn feed(&mut self, chunk: &str) {
+        let Counter {
+            tokenizer,
+            freqs,
+            pending,
+        } = self;
+        let mut emit = |token: &str| crate::increment(freqs, token);
+
+        let mut rest = chunk;
+        while let Some(i) = rest.find('\n') {
+            let line = &rest[..i];
+            if pending.is_empty() {
+                tokenizer.tokenize(strip_cr(line), &mut emit);
+            } else {
+                pending.push_str(line);
+                tokenizer.tokenize(strip_cr(pending), &mut emit);
+                pending.clear();
+            }
+            rest = &rest[i + 1..];
+        }
+        pending.push_str(rest);
+    }
+
+    /// `other`が数えた出現頻度を加える。
+    ///
+    /// `other`の改行で終わっていない最後の行は、`other`の入力の終わりとして数える。
+    pub fn merge(&mut self, other: Counter) {
+        for (token, count) in other.finish() {
+            *self.freqs.entry(token).or_insert(0) += count;
+        }
+    }
+
+    /// これまでに数えた出現頻度を返す。
+    ///
+    /// 改行で終わっていない最後の行と、トークナイザーが行をまたいで保持しているトークンは含まない。
+    pub fn frequencies(&self) -> &HashMap<String, usize> {
+        &self.freqs
+    }
+
+    /// 入力の終わりとして、改行で終わっていない最後の行を数え、出現頻度を返す。
+    pub fn finish(mut self) -> HashMap<String, usize> {
+        let Counter {
+            tokenizer,
+            freqs,
+            pending,
+        } = &mut self;
+        let mut emit = |token: &str| crate::increment(freqs, token);
+        // 改行が続かない`\r`は、[`count`](fn.count.html)と同様に行の一部として数える。
+        if !pending.is_empty() {
+            tokenizer.tokenize(pending, &mut emit);
+        }
+        tokenizer.finish(&mut emit);
+        self.freqs
+    }
+}
+
+/// オプションのデフォルトは、[`count`](fn.count.html)と同じ[`word`](enum.CountOption.html#variant.Word)。
+impl Default for Counter {
+    fn default() -> Self {
+        Counter::new(CountOption::default())
+    }
+}
+
+impl fmt::Debug for Counter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Counter")
+            .field("freqs", &self.freqs)
+            .field("pending", &self.pending)
+            .finish_non_exhaustive()
+    }
+}
+
+/// 行末に残った`\r`を取り除く。
+fn strip_cr(line: &str) -> &str {
+    line.strip_suffix('\r').unwrap_or(line)
+}
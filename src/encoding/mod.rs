@@ -0,0 +1,833 @@
+//! UTF-8以外の文字コードで記録された入力を、UTF-8文字列に変換する機能を提供する。
+//!
+//! [`count`](../fn.count.html)は入力がUTF-8文字列であることを前提としているため、
+//! Shift_JISなどで記録された入力は、[`Decoder`](struct.Decoder.html)を経由して渡す。
+//!
+//! ```
+//! use std::io::Cursor;
+//! use kuroyasu_bicycle_book_wordcount::encoding::{Decoder, Encoding};
+//! use kuroyasu_bicycle_book_wordcount::{count, CountOption};
+//!
+//! // Shift_JISで記録された「あいあ」
+//! let input = Cursor::new(b"\x82\xa0\x82\xa2\x82\xa0");
+//! let freqs = count(Decoder::new(input, Encoding::ShiftJis), CountOption::Char);
+//! assert_eq!(freqs["あ"], 2);
+//! assert_eq!(freqs["い"], 1);
+//! ```
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead, Read};
+use std::str::FromStr;
+
+mod tables;
+
+/// 文字コードの自動判別に使用する入力の先頭部分の大きさ
+const DETECTION_SAMPLE_SIZE: usize = 64 * 1024;
+
+/// 入力から一度に読み込むバイト数
+const READ_CHUNK_SIZE: usize = 8 * 1024;
+
+/// [`Decoder`](struct.Decoder.html)が扱う文字コード
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    /// UTF-8。BOMの有無は問わない。
+    Utf8,
+    /// Shift_JIS。Windowsの拡張（CP932）を含む。
+    ShiftJis,
+    /// EUC-JP。JIS X 0212の補助漢字を含む。
+    EucJp,
+    /// ISO-2022-JP（JISコード）。
+    Iso2022Jp,
+    /// リトルエンディアンのUTF-16。BOMの有無は問わない。
+    Utf16Le,
+    /// ビッグエンディアンのUTF-16。BOMの有無は問わない。
+    Utf16Be,
+}
+
+impl Encoding {
+    /// `utf-8`や`cp932`などの文字コードの名前から、文字コードを返す。
+    ///
+    /// 名前の大文字と小文字は区別しない。名前が不明な場合は`None`を返す。
+    pub fn from_label(label: &str) -> Option<Encoding> {
+        match label.trim().to_ascii_lowercase().as_str() {
+            "utf-8" | "utf8" => Some(Encoding::Utf8),
+            "shift_jis" | "shift-jis" | "sjis" | "cp932" | "ms932" | "windows-31j" => {
+                Some(Encoding::ShiftJis)
+            }
+            "euc-jp" | "euc_jp" | "eucjp" => Some(Encoding::EucJp),
+            "iso-2022-jp" | "iso2022jp" | "jis" => Some(Encoding::Iso2022Jp),
+            "utf-16le" | "utf16le" | "utf-16" | "utf16" => Some(Encoding::Utf16Le),
+            "utf-16be" | "utf16be" => Some(Encoding::Utf16Be),
+            _ => None,
+        }
+    }
+
+    /// 文字コードの名前を返す。
+    pub fn name(&self) -> &'static str {
+        match self {
+            Encoding::Utf8 => "UTF-8",
+            Encoding::ShiftJis => "Shift_JIS",
+            Encoding::EucJp => "EUC-JP",
+            Encoding::Iso2022Jp => "ISO-2022-JP",
+            Encoding::Utf16Le => "UTF-16LE",
+            Encoding::Utf16Be => "UTF-16BE",
+        }
+    }
+
+    /// 文字コードのBOMを返す。BOMがない文字コードの場合は空のスライスを返す。
+    fn bom(&self) -> &'static [u8] {
+        match self {
+            Encoding::Utf8 => b"\xef\xbb\xbf",
+            Encoding::Utf16Le => b"\xff\xfe",
+            Encoding::Utf16Be => b"\xfe\xff",
+            _ => b"",
+        }
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Encoding {
+    type Err = UnknownEncoding;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Encoding::from_label(s).ok_or_else(|| UnknownEncoding(s.to_string()))
+    }
+}
+
+/// 文字コードの名前が不明であることを示すエラー
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownEncoding(String);
+
+impl fmt::Display for UnknownEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "不明な文字コードです: {}", self.0)
+    }
+}
+
+impl Error for UnknownEncoding {}
+
+/// 入力を文字列に変換できなかったことを示すエラー
+///
+/// [`Decoder`](struct.Decoder.html)の読み込みで発生する`io::Error`は、
+/// 種類が`io::ErrorKind::InvalidData`で、このエラーを内部に保持している。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodeError {
+    encoding: Encoding,
+    offset: usize,
+}
+
+impl DecodeError {
+    /// 変換しようとした文字コードを返す。
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// 変換できなかったバイトの、入力の先頭からの位置（0始まり）を返す。
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}バイト目を{}として変換できません",
+            self.offset, self.encoding
+        )
+    }
+}
+
+impl Error for DecodeError {}
+
+/// 入力の先頭部分から、文字コードを推測する。
+///
+/// BOMがあればBOMに従う。BOMがない場合は、エスケープシーケンスの有無、UTF-16に特有の
+/// `0x00`の出現位置、UTF-8としての妥当性を順に調べ、いずれにも当てはまらない場合は、
+/// Shift_JISとEUC-JPのうち変換できないバイトが少ない方（同数の場合はかなが多く現れる方）を選ぶ。
+///
+/// ```
+/// use kuroyasu_bicycle_book_wordcount::encoding::{detect, Encoding};
+///
+/// assert_eq!(detect("あいう".as_bytes()), Encoding::Utf8);
+/// assert_eq!(detect(b"\x82\xa0\x82\xa2\x82\xa4"), Encoding::ShiftJis);
+/// assert_eq!(detect(b"\xa4\xa2\xa4\xa4\xa4\xa6"), Encoding::EucJp);
+/// ```
+pub fn detect(sample: &[u8]) -> Encoding {
+    for encoding in [Encoding::Utf8, Encoding::Utf16Le, Encoding::Utf16Be] {
+        if sample.starts_with(encoding.bom()) {
+            return encoding;
+        }
+    }
+
+    if sample.is_ascii() && sample.windows(2).any(|w| w == b"\x1b$" || w == b"\x1b(") {
+        return Encoding::Iso2022Jp;
+    }
+
+    let pairs = sample.len() / 2;
+    if pairs > 0 {
+        let even = sample.iter().step_by(2).filter(|&&b| b == 0).count();
+        let odd = sample
+            .iter()
+            .skip(1)
+            .step_by(2)
+            .filter(|&&b| b == 0)
+            .count();
+        if odd * 3 > pairs && even * 3 <= pairs {
+            return Encoding::Utf16Le;
+        }
+        if even * 3 > pairs && odd * 3 <= pairs {
+            return Encoding::Utf16Be;
+        }
+    }
+
+    match std::str::from_utf8(sample) {
+        Ok(_) => return Encoding::Utf8,
+        // 末尾で文字が途切れているだけであれば、UTF-8とみなす。
+        Err(e) if e.error_len().is_none() => return Encoding::Utf8,
+        Err(_) => {}
+    }
+
+    let score = |encoding| {
+        let mut text = String::new();
+        let mut replacements = 0;
+        let mut state = Iso2022JpState::Ascii;
+        let mut output = Output {
+            text: &mut text,
+            lossy: true,
+            replacements: &mut replacements,
+        };
+        let _ = decode(encoding, &mut state, sample, true, &mut output);
+        let kana = text
+            .chars()
+            .filter(|c| matches!(c, '\u{3041}'..='\u{30ff}'))
+            .count();
+        (replacements, std::cmp::Reverse(kana))
+    };
+    if score(Encoding::EucJp) < score(Encoding::ShiftJis) {
+        Encoding::EucJp
+    } else {
+        Encoding::ShiftJis
+    }
+}
+
+/// 入力を指定された文字コードから変換し、UTF-8文字列として読み込むリーダー
+///
+/// `BufRead`を実装しているため、そのまま[`count`](../fn.count.html)に渡すことができる。
+/// 既定では変換できないバイトを見つけると、`io::ErrorKind::InvalidData`のエラーを返すが、
+/// [`lossy`](#method.lossy)を有効にすると、代わりに`U+FFFD`に置き換えて読み込みを続ける。
+///
+/// 入力の先頭にある文字コードのBOMは取り除かれる。
+pub struct Decoder<R> {
+    inner: R,
+    encoding: Encoding,
+    lossy: bool,
+    replacements: usize,
+    /// まだ変換していない入力
+    raw: Vec<u8>,
+    /// `raw`の先頭の、入力の先頭からの位置
+    raw_offset: usize,
+    eof: bool,
+    bom_checked: bool,
+    iso2022jp: Iso2022JpState,
+    /// 変換済みの文字列
+    text: String,
+    /// `text`のうち、読み込み済みのバイト数
+    pos: usize,
+    error: Option<DecodeError>,
+}
+
+impl<R: Read> Decoder<R> {
+    /// 入力を`encoding`から変換するリーダーを作成する。
+    pub fn new(inner: R, encoding: Encoding) -> Self {
+        Decoder {
+            inner,
+            encoding,
+            lossy: false,
+            replacements: 0,
+            raw: Vec::new(),
+            raw_offset: 0,
+            eof: false,
+            bom_checked: false,
+            iso2022jp: Iso2022JpState::Ascii,
+            text: String::new(),
+            pos: 0,
+            error: None,
+        }
+    }
+
+    /// 入力の先頭部分から文字コードを推測して、リーダーを作成する。
+    ///
+    /// 推測の方法は、[`detect`](fn.detect.html)を参照すること。
+    ///
+    /// # Errors
+    ///
+    /// 推測のための入力の読み込みに失敗した場合は、エラーを返す。
+    pub fn detect(mut inner: R) -> io::Result<Self> {
+        let mut sample = Vec::with_capacity(DETECTION_SAMPLE_SIZE);
+        let eof = (&mut inner)
+            .take(DETECTION_SAMPLE_SIZE as u64)
+            .read_to_end(&mut sample)?
+            < DETECTION_SAMPLE_SIZE;
+        let mut decoder = Decoder::new(inner, detect(&sample));
+        decoder.raw = sample;
+        decoder.eof = eof;
+        Ok(decoder)
+    }
+
+    /// 変換できないバイトを`U+FFFD`に置き換えるかどうかを設定する。
+    pub fn lossy(mut self, lossy: bool) -> Self {
+        self.lossy = lossy;
+        self
+    }
+
+    /// 入力の文字コードを返す。
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// これまでに`U+FFFD`に置き換えた回数を返す。
+    pub fn replacements(&self) -> usize {
+        self.replacements
+    }
+
+    /// 内部のリーダーを返す。
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// まだ変換していない入力を、可能な限り変換する。
+    fn decode_raw(&mut self) {
+        if !self.bom_checked {
+            let bom = self.encoding.bom();
+            if self.raw.len() < bom.len() && !self.eof {
+                return;
+            }
+            if self.raw.starts_with(bom) {
+                self.raw.drain(..bom.len());
+                self.raw_offset += bom.len();
+            }
+            self.bom_checked = true;
+        }
+
+        let mut output = Output {
+            text: &mut self.text,
+            lossy: self.lossy,
+            replacements: &mut self.replacements,
+        };
+        let result = decode(
+            self.encoding,
+            &mut self.iso2022jp,
+            &self.raw,
+            self.eof,
+            &mut output,
+        );
+        match result {
+            Ok(consumed) => {
+                self.raw.drain(..consumed);
+                self.raw_offset += consumed;
+            }
+            Err(at) => {
+                self.error = Some(DecodeError {
+                    encoding: self.encoding,
+                    offset: self.raw_offset + at,
+                });
+                // エラーの後は、入力を読み込まない。
+                self.raw.clear();
+                self.eof = true;
+            }
+        }
+    }
+}
+
+impl<R: Read> Read for Decoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl<R: Read> BufRead for Decoder<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        while self.pos == self.text.len() {
+            self.text.clear();
+            self.pos = 0;
+            if !self.raw.is_empty() || self.eof {
+                self.decode_raw();
+            }
+            if !self.text.is_empty() {
+                break;
+            }
+            if let Some(e) = self.error.take() {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, e));
+            }
+            if self.eof {
+                break;
+            }
+            let mut chunk = [0; READ_CHUNK_SIZE];
+            let n = self.inner.read(&mut chunk)?;
+            if n == 0 {
+                self.eof = true;
+            }
+            self.raw.extend_from_slice(&chunk[..n]);
+        }
+        Ok(&self.text.as_bytes()[self.pos..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos = (self.pos + amt).min(self.text.len());
+    }
+}
+
+/// 変換した文字の出力先
+struct Output<'a> {
+    text: &'a mut String,
+    lossy: bool,
+    replacements: &'a mut usize,
+}
+
+impl Output<'_> {
+    fn push(&mut self, c: char) {
+        self.text.push(c);
+    }
+
+    /// `at`の位置のバイトを変換できなかったことを記録する。
+    ///
+    /// 置き換えが有効でない場合は、`Err(at)`を返す。
+    fn invalid(&mut self, at: usize) -> Result<(), usize> {
+        if self.lossy {
+            self.text.push(char::REPLACEMENT_CHARACTER);
+            *self.replacements += 1;
+            Ok(())
+        } else {
+            Err(at)
+        }
+    }
+}
+
+/// ISO-2022-JPのエスケープシーケンスで切り替わる文字集合
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Iso2022JpState {
+    Ascii,
+    Roman,
+    Katakana,
+    Jis0208,
+}
+
+/// `input`を変換して`output`に出力し、変換したバイト数を返す。
+///
+/// `last`が`false`の場合、末尾で途切れている文字は変換せずに残す。
+/// 変換できないバイトがあり、置き換えが有効でない場合は、その位置を`Err`で返す。
+fn decode(
+    encoding: Encoding,
+    state: &mut Iso2022JpState,
+    input: &[u8],
+    last: bool,
+    output: &mut Output<'_>,
+) -> Result<usize, usize> {
+    match encoding {
+        Encoding::Utf8 => decode_utf8(input, last, output),
+        Encoding::ShiftJis => decode_shift_jis(input, last, output),
+        Encoding::EucJp => decode_euc_jp(input, last, output),
+        Encoding::Iso2022Jp => decode_iso_2022_jp(state, input, last, output),
+        Encoding::Utf16Le => decode_utf16(input, last, output, u16::from_le_bytes),
+        Encoding::Utf16Be => decode_utf16(input, last, output, u16::from_be_bytes),
+    }
+}
+
+fn decode_utf8(input: &[u8], last: bool, output: &mut Output<'_>) -> Result<usize, usize> {
+    let mut i = 0;
+    loop {
+        match std::str::from_utf8(&input[i..]) {
+            Ok(s) => {
+                output.text.push_str(s);
+                return Ok(input.len());
+            }
+            Err(e) => {
+                let valid = i + e.valid_up_to();
+                output
+                    .text
+                    .push_str(std::str::from_utf8(&input[i..valid]).unwrap());
+                match e.error_len() {
+                    None if !last => return Ok(valid),
+                    None => {
+                        output.invalid(valid)?;
+                        return Ok(input.len());
+                    }
+                    Some(len) => {
+                        output.invalid(valid)?;
+                        i = valid + len;
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn decode_utf16(
+    input: &[u8],
+    last: bool,
+    output: &mut Output<'_>,
+    unit: fn([u8; 2]) -> u16,
+) -> Result<usize, usize> {
+    let unit_at = |i: usize| unit([input[i], input[i + 1]]);
+    let mut i = 0;
+    while i + 2 <= input.len() {
+        let u = unit_at(i);
+        match u {
+            0xD800..=0xDBFF => {
+                if i + 4 > input.len() {
+                    if !last {
+                        return Ok(i);
+                    }
+                    output.invalid(i)?;
+                    i += 2;
+                    continue;
+                }
+                let low = unit_at(i + 2);
+                if (0xDC00..=0xDFFF).contains(&low) {
+                    let c = 0x10000 + ((u32::from(u) - 0xD800) << 10) + (u32::from(low) - 0xDC00);
+                    output.push(char::from_u32(c).unwrap());
+                    i += 4;
+                } else {
+                    output.invalid(i)?;
+                    i += 2;
+                }
+            }
+            0xDC00..=0xDFFF => {
+                output.invalid(i)?;
+                i += 2;
+            }
+            _ => {
+                output.push(char::from_u32(u32::from(u)).unwrap());
+                i += 2;
+            }
+        }
+    }
+    if i < input.len() {
+        if !last {
+            return Ok(i);
+        }
+        output.invalid(i)?;
+        i = input.len();
+    }
+    Ok(i)
+}
+
+/// JIS X 0208のポインタから文字を返す。
+fn jis0208(pointer: usize) -> Option<char> {
+    tables::JIS0208
+        .get(pointer)
+        .filter(|&&c| c != 0)
+        .and_then(|&c| char::from_u32(u32::from(c)))
+}
+
+/// JIS X 0212のポインタから文字を返す。
+fn jis0212(pointer: usize) -> Option<char> {
+    tables::JIS0212
+        .get(pointer)
+        .filter(|&&c| c != 0)
+        .and_then(|&c| char::from_u32(u32::from(c)))
+}
+
+/// 半角カタカナの1バイト目（`0xA1`の位置）に対応する文字のコードポイント
+const HALFWIDTH_KATAKANA_BASE: u32 = 0xFF61;
+
+fn halfwidth_katakana(offset: u8) -> char {
+    char::from_u32(HALFWIDTH_KATAKANA_BASE + u32::from(offset)).unwrap()
+}
+
+fn decode_shift_jis(input: &[u8], last: bool, output: &mut Output<'_>) -> Result<usize, usize> {
+    let mut i = 0;
+    while i < input.len() {
+        let b = input[i];
+        match b {
+            0x00..=0x80 => {
+                output.push(char::from(b));
+                i += 1;
+            }
+            0xA1..=0xDF => {
+                output.push(halfwidth_katakana(b - 0xA1));
+                i += 1;
+            }
+            0x81..=0x9F | 0xE0..=0xFC => {
+                let Some(&t) = input.get(i + 1) else {
+                    if !last {
+                        return Ok(i);
+                    }
+                    output.invalid(i)?;
+                    i += 1;
+                    continue;
+                };
+                if matches!(t, 0x40..=0x7E | 0x80..=0xFC) {
+                    let lead_offset = if b < 0xA0 { 0x81 } else { 0xC1 };
+                    let trail_offset = if t < 0x7F { 0x40 } else { 0x41 };
+                    let pointer =
+                        usize::from(b - lead_offset) * 188 + usize::from(t - trail_offset);
+                    let c = if (8836..=10715).contains(&pointer) {
+                        // 外字は私用領域に対応させる。
+                        char::from_u32(0xE000 + (pointer - 8836) as u32)
+                    } else {
+                        jis0208(pointer)
+                    };
+                    if let Some(c) = c {
+                        output.push(c);
+                        i += 2;
+                        continue;
+                    }
+                }
+                output.invalid(i)?;
+                // 2バイト目がASCIIの場合は、ASCIIとして読み直す。
+                i += if t < 0x80 { 1 } else { 2 };
+            }
+            _ => {
+                output.invalid(i)?;
+                i += 1;
+            }
+        }
+    }
+    Ok(i)
+}
+
+fn decode_euc_jp(input: &[u8], last: bool, output: &mut Output<'_>) -> Result<usize, usize> {
+    let mut i = 0;
+    while i < input.len() {
+        let b = input[i];
+        let len = match b {
+            0x00..=0x7F => {
+                output.push(char::from(b));
+                i += 1;
+                continue;
+            }
+            0x8F => 3,
+            0x8E | 0xA1..=0xFE => 2,
+            _ => {
+                output.invalid(i)?;
+                i += 1;
+                continue;
+            }
+        };
+        if i + len > input.len() {
+            if !last {
+                return Ok(i);
+            }
+            output.invalid(i)?;
+            i += 1;
+            continue;
+        }
+        let trail = &input[i + 1..i + len];
+        let c = match (b, trail) {
+            (0x8E, &[t]) if (0xA1..=0xDF).contains(&t) => Some(halfwidth_katakana(t - 0xA1)),
+            (0x8F, &[t1, t2]) if (0xA1..=0xFE).contains(&t1) && (0xA1..=0xFE).contains(&t2) => {
+                jis0212(usize::from(t1 - 0xA1) * 94 + usize::from(t2 - 0xA1))
+            }
+            (0xA1..=0xFE, &[t]) if (0xA1..=0xFE).contains(&t) => {
+                jis0208(usize::from(b - 0xA1) * 94 + usize::from(t - 0xA1))
+            }
+            _ => None,
+        };
+        match c {
+            Some(c) => {
+                output.push(c);
+                i += len;
+            }
+            None => {
+                output.invalid(i)?;
+                i += 1;
+            }
+        }
+    }
+    Ok(i)
+}
+
+fn decode_iso_2022_jp(
+    state: &mut Iso2022JpState,
+    input: &[u8],
+    last: bool,
+    output: &mut Output<'_>,
+) -> Result<usize, usize> {
+    let mut i = 0;
+    while i < input.len() {
+        let b = input[i];
+        if b == 0x1B {
+            let sequence = &input[i..input.len().min(i + 3)];
+            let next = match sequence {
+                b"\x1b(B" => Some(Iso2022JpState::Ascii),
+                b"\x1b(J" => Some(Iso2022JpState::Roman),
+                b"\x1b(I" => Some(Iso2022JpState::Katakana),
+                b"\x1b$@" | b"\x1b$B" => Some(Iso2022JpState::Jis0208),
+                _ => None,
+            };
+            match next {
+                Some(next) => {
+                    *state = next;
+                    i += 3;
+                }
+                None if sequence.len() < 3 && !last => return Ok(i),
+                None => {
+                    output.invalid(i)?;
+                    i += 1;
+                }
+            }
+            continue;
+        }
+        // 改行は、どの文字集合でもそのまま出力する。
+        if b == b'\n' || b == b'\r' {
+            output.push(char::from(b));
+            i += 1;
+            continue;
+        }
+        match *state {
+            Iso2022JpState::Ascii if b < 0x80 => {
+                output.push(char::from(b));
+                i += 1;
+            }
+            Iso2022JpState::Roman if b < 0x80 => {
+                output.push(match b {
+                    0x5C => '\u{A5}',
+                    0x7E => '\u{203E}',
+                    _ => char::from(b),
+                });
+                i += 1;
+            }
+            Iso2022JpState::Katakana if (0x21..=0x5F).contains(&b) => {
+                output.push(halfwidth_katakana(b - 0x21));
+                i += 1;
+            }
+            Iso2022JpState::Jis0208 if (0x21..=0x7E).contains(&b) => {
+                let Some(&t) = input.get(i + 1) else {
+                    if !last {
+                        return Ok(i);
+                    }
+                    output.invalid(i)?;
+                    i += 1;
+                    continue;
+                };
+                match Some(t)
+                    .filter(|t| (0x21..=0x7E).contains(t))
+                    .and_then(|t| jis0208(usize::from(b - 0x21) * 94 + usize::from(t - 0x21)))
+                {
+                    Some(c) => {
+                        output.push(c);
+                        i += 2;
+                    }
+                    None => {
+                        output.invalid(i)?;
+                        i += 1;
+                    }
+                }
+            }
+            _ => {
+                output.invalid(i)?;
+                i += 1;
+            }
+        }
+    }
+    Ok(i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn decode_all(input: &[u8], encoding: Encoding) -> io::Result<String> {
+        let mut text = String::new();
+        Decoder::new(Cursor::new(input), encoding).read_to_string(&mut text)?;
+        Ok(text)
+    }
+
+    #[test]
+    fn decode_japanese_encodings() {
+        let expected = "漢字とｶﾅ、①";
+        assert_eq!(
+            decode_all(
+                b"\x8a\xbf\x8e\x9a\x82\xc6\xb6\xc5\x81\x41\x87\x40",
+                Encoding::ShiftJis
+            )
+            .unwrap(),
+            expected
+        );
+        assert_eq!(
+            decode_all(
+                b"\xb4\xc1\xbb\xfa\xa4\xc8\x8e\xb6\x8e\xc5\xa1\xa2\xad\xa1",
+                Encoding::EucJp
+            )
+            .unwrap(),
+            expected
+        );
+        assert_eq!(
+            decode_all(
+                b"\x1b$B4A;z$H\x1b(I6E\x1b$B!\"-!\x1b(B",
+                Encoding::Iso2022Jp
+            )
+            .unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn decode_utf16_with_bom() {
+        let le = [0xff, 0xfe, 0x42, 0x30, 0x3d, 0xd8, 0x00, 0xde];
+        assert_eq!(decode_all(&le, Encoding::Utf16Le).unwrap(), "あ😀");
+        let mut decoder = Decoder::detect(Cursor::new(le)).unwrap();
+        assert_eq!(decoder.encoding(), Encoding::Utf16Le);
+        let mut text = String::new();
+        decoder.read_to_string(&mut text).unwrap();
+        assert_eq!(text, "あ😀");
+
+        let be = [0xfe, 0xff, 0x30, 0x42];
+        assert_eq!(decode_all(&be, Encoding::Utf16Be).unwrap(), "あ");
+    }
+
+    #[test]
+    fn decode_strips_utf8_bom() {
+        assert_eq!(
+            decode_all(b"\xef\xbb\xbfabc", Encoding::Utf8).unwrap(),
+            "abc"
+        );
+    }
+
+    #[test]
+    fn decode_error_has_offset() {
+        let err = decode_all(b"ab\x82\xa0\xff", Encoding::ShiftJis).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let err = err
+            .get_ref()
+            .unwrap()
+            .downcast_ref::<DecodeError>()
+            .unwrap();
+        assert_eq!(err.offset(), 4);
+    }
+
+    #[test]
+    fn decode_lossy_counts_replacements() {
+        let mut decoder = Decoder::new(Cursor::new(b"a\xffb\x82"), Encoding::ShiftJis).lossy(true);
+        let mut text = String::new();
+        decoder.read_to_string(&mut text).unwrap();
+        assert_eq!(text, "a\u{fffd}b\u{fffd}");
+        assert_eq!(decoder.replacements(), 2);
+    }
+
+    #[test]
+    fn decode_across_chunk_boundary() {
+        // 読み込みの境界で2バイト文字が途切れても、正しく変換する。
+        let input: Vec<u8> = b"\x82\xa0".repeat(READ_CHUNK_SIZE);
+        let text = decode_all(&input, Encoding::ShiftJis).unwrap();
+        assert_eq!(text.chars().count(), READ_CHUNK_SIZE);
+        assert!(text.chars().all(|c| c == 'あ'));
+    }
+
+    #[test]
+    fn detect_iso_2022_jp() {
+        assert_eq!(detect(b"\x1b$B$\"\x1b(B"), Encoding::Iso2022Jp);
+    }
+}
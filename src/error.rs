@@ -0,0 +1,88 @@
+//! [`try_count`](../fn.try_count.html)が返すエラー型。
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+/// 出現頻度を数える途中で発生したエラー
+///
+/// `line`は1から始まる行番号、`offset`は入力の先頭から数えた問題のバイトの位置
+/// （0始まり）を示す。
+#[derive(Debug)]
+pub enum CountError {
+    /// 入力の読み込みに失敗した。
+    Io {
+        line: usize,
+        offset: usize,
+        source: io::Error,
+    },
+    /// 入力を文字列としてデコードできなかった。
+    ///
+    /// [`encoding::Decoder`](../encoding/struct.Decoder.html)による変換に失敗した場合、
+    /// `offset`は変換前の入力での位置を示す。
+    Decode { line: usize, offset: usize },
+}
+
+impl CountError {
+    /// エラーが発生した行の行番号（1始まり）を返す。
+    pub fn line(&self) -> usize {
+        match self {
+            CountError::Io { line, .. } | CountError::Decode { line, .. } => *line,
+        }
+    }
+
+    /// エラーが発生したバイトの入力の先頭からの位置（0始まり）を返す。
+    pub fn offset(&self) -> usize {
+        match self {
+            CountError::Io { offset, .. } | CountError::Decode { offset, .. } => *offset,
+        }
+    }
+
+    /// 入力の途中から読み込んだ場合に、先に読み込んだ`lines`行と`bytes`バイトを位置に加える。
+    pub(crate) fn shifted(self, lines: usize, bytes: usize) -> CountError {
+        match self {
+            CountError::Io {
+                line,
+                offset,
+                source,
+            } => CountError::Io {
+                line: line + lines,
+                offset: offset + bytes,
+                source,
+            },
+            CountError::Decode { line, offset } => CountError::Decode {
+                line: line + lines,
+                offset: offset + bytes,
+            },
+        }
+    }
+}
+
+impl fmt::Display for CountError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CountError::Io {
+                line,
+                offset,
+                source,
+            } => write!(
+                f,
+                "{}行目（{}バイト目）の読み込みに失敗しました: {}",
+                line, offset, source
+            ),
+            CountError::Decode { line, offset } => write!(
+                f,
+                "{}行目（{}バイト目）を文字列としてデコードできません",
+                line, offset
+            ),
+        }
+    }
+}
+
+impl Error for CountError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CountError::Io { source, .. } => Some(source),
+            CountError::Decode { .. } => None,
+        }
+    }
+}
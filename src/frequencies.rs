@@ -0,0 +1,215 @@
+use std::collections::hash_map;
+use std::collections::HashMap;
+use std::iter::FromIterator;
+use std::ops::Index;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// トークンごとの出現頻度
+///
+/// [`count`](fn.count.html)が返す`HashMap`を包み、出現頻度順の並べ替えや合計などを提供する。
+/// `HashMap`とは`From`で相互に変換できる。
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use kuroyasu_bicycle_book_wordcount::{count, CountOption, Frequencies};
+///
+/// let freqs = Frequencies::from(count(Cursor::new("aa bb cc bb aa bb"), CountOption::Word));
+/// assert_eq!(freqs.total(), 6);
+/// assert_eq!(freqs.distinct(), 3);
+/// assert_eq!(freqs.most_common(2), [("bb", 3), ("aa", 2)]);
+/// assert_eq!(freqs.relative("bb"), 0.5);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "HashMap<String, usize>", into = "HashMap<String, usize>"))]
+pub struct Frequencies {
+    map: HashMap<String, usize>,
+    total: usize,
+}
+
+impl Frequencies {
+    /// 空の出現頻度を作成する。
+    pub fn new() -> Self {
+        Frequencies::default()
+    }
+
+    /// 全てのトークンの出現頻度の合計を返す。
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// 異なるトークンの数を返す。
+    pub fn distinct(&self) -> usize {
+        self.map.len()
+    }
+
+    /// トークンがなければ`true`を返す。
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// `key`の出現頻度を返す。出現していないトークンは`0`を返す。
+    pub fn get(&self, key: &str) -> usize {
+        self.map.get(key).copied().unwrap_or(0)
+    }
+
+    /// `key`の出現頻度の、全体の出現頻度の合計に対する割合を返す。
+    ///
+    /// 出現していないトークン、またはトークンがない場合は`0.0`を返す。
+    pub fn relative(&self, key: &str) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        self.get(key) as f64 / self.total as f64
+    }
+
+    /// 全てのトークンを、出現頻度が高い順に並べて返す。
+    ///
+    /// 出現頻度が同じトークンは、辞書順に並べる。このため、結果の順序は常に同じになる。
+    pub fn sorted_by_count(&self) -> Vec<(&str, usize)> {
+        let mut sorted: Vec<_> = self.iter().collect();
+        sorted.sort_unstable_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        sorted
+    }
+
+    /// 出現頻度が高い順に、最大`n`個のトークンを返す。順序は[`sorted_by_count`](#method.sorted_by_count)と同じ。
+    pub fn most_common(&self, n: usize) -> Vec<(&str, usize)> {
+        let mut sorted = self.sorted_by_count();
+        sorted.truncate(n);
+        sorted
+    }
+
+    /// 出現頻度が`min`以上のトークンだけを残す。
+    pub fn retain_min_count(&mut self, min: usize) {
+        self.map.retain(|_, &mut count| count >= min);
+        self.total = self.map.values().sum();
+    }
+
+    /// 出現頻度が`min`以上のトークンだけからなる出現頻度を返す。
+    pub fn with_min_count(mut self, min: usize) -> Self {
+        self.retain_min_count(min);
+        self
+    }
+
+    /// トークンと出現頻度を、順不同で返すイテレーターを返す。
+    pub fn iter(&self) -> FrequencyIter<'_> {
+        FrequencyIter {
+            inner: self.map.iter(),
+        }
+    }
+
+    /// 包んでいる`HashMap`への参照を返す。
+    pub fn as_map(&self) -> &HashMap<String, usize> {
+        &self.map
+    }
+}
+
+impl From<HashMap<String, usize>> for Frequencies {
+    fn from(map: HashMap<String, usize>) -> Self {
+        let total = map.values().sum();
+        Frequencies { map, total }
+    }
+}
+
+impl From<Frequencies> for HashMap<String, usize> {
+    fn from(freqs: Frequencies) -> Self {
+        freqs.map
+    }
+}
+
+/// 同じトークンが複数回現れた場合は、出現頻度を合計する。
+impl FromIterator<(String, usize)> for Frequencies {
+    fn from_iter<I: IntoIterator<Item = (String, usize)>>(iter: I) -> Self {
+        let mut freqs = Frequencies::new();
+        freqs.extend(iter);
+        freqs
+    }
+}
+
+/// 同じトークンが既にある場合は、出現頻度を加える。
+impl Extend<(String, usize)> for Frequencies {
+    fn extend<I: IntoIterator<Item = (String, usize)>>(&mut self, iter: I) {
+        for (key, count) in iter {
+            *self.map.entry(key).or_insert(0) += count;
+            self.total += count;
+        }
+    }
+}
+
+/// 出現していないトークンは`0`を返す。
+impl Index<&str> for Frequencies {
+    type Output = usize;
+
+    fn index(&self, key: &str) -> &usize {
+        self.map.get(key).unwrap_or(&0)
+    }
+}
+
+/// [`Frequencies::iter`](struct.Frequencies.html#method.iter)が返すイテレーター
+#[derive(Debug, Clone)]
+pub struct FrequencyIter<'a> {
+    inner: hash_map::Iter<'a, String, usize>,
+}
+
+impl<'a> Iterator for FrequencyIter<'a> {
+    type Item = (&'a str, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(key, &count)| (key.as_str(), count))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a> IntoIterator for &'a Frequencies {
+    type Item = (&'a str, usize);
+    type IntoIter = FrequencyIter<'a>;
+
+    fn into_iter(self) -> FrequencyIter<'a> {
+        self.iter()
+    }
+}
+
+impl IntoIterator for Frequencies {
+    type Item = (String, usize);
+    type IntoIter = hash_map::IntoIter<String, usize>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.map.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn freqs(pairs: &[(&str, usize)]) -> Frequencies {
+        pairs.iter().map(|&(k, c)| (k.to_string(), c)).collect()
+    }
+
+    #[test]
+    fn sorted_with_deterministic_ties() {
+        let f = freqs(&[("b", 2), ("c", 1), ("a", 2), ("d", 3)]);
+        assert_eq!(
+            f.sorted_by_count(),
+            [("d", 3), ("a", 2), ("b", 2), ("c", 1)]
+        );
+        assert_eq!(f.most_common(10).len(), 4);
+    }
+
+    #[test]
+    fn min_count_updates_total() {
+        let f = freqs(&[("a", 1), ("b", 2), ("a", 2)]).with_min_count(3);
+        assert_eq!(f.distinct(), 1);
+        assert_eq!(f.total(), 3);
+        assert_eq!(f["a"], 3);
+        assert_eq!(f["b"], 0);
+        assert_eq!(HashMap::from(f).len(), 1);
+    }
+}
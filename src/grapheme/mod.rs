@@ -0,0 +1,159 @@
+//! Unicode標準附属書29（UAX #29）に従って、文字列を拡張書記素クラスタに分割する機能を提供する。
+mod tables;
+
+/// 書記素クラスタの境界の判定に使用する文字の分類（Grapheme_Cluster_Break）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Category {
+    Other,
+    CR,
+    LF,
+    Control,
+    Extend,
+    Zwj,
+    RegionalIndicator,
+    Prepend,
+    SpacingMark,
+    L,
+    V,
+    T,
+    Lv,
+    Lvt,
+    ExtendedPictographic,
+}
+
+fn category(c: char) -> Category {
+    let c = c as u32;
+    tables::CATEGORIES
+        .binary_search_by(|&(start, end, _)| {
+            if end < c {
+                std::cmp::Ordering::Less
+            } else if start > c {
+                std::cmp::Ordering::Greater
+            } else {
+                std::cmp::Ordering::Equal
+            }
+        })
+        .map_or(Category::Other, |i| tables::CATEGORIES[i].2)
+}
+
+/// 文字列を拡張書記素クラスタごとに分割するイテレーター
+///
+/// [`graphemes`](fn.graphemes.html)で作成する。
+#[derive(Debug, Clone)]
+pub struct Graphemes<'a> {
+    rest: &'a str,
+}
+
+/// `s`を拡張書記素クラスタごとに分割するイテレーターを返す。
+///
+/// ```
+/// use kuroyasu_bicycle_book_wordcount::grapheme::graphemes;
+///
+/// let clusters: Vec<_> = graphemes("か\u{3099}🇯🇵👨‍👩‍👧").collect();
+/// assert_eq!(clusters, ["か\u{3099}", "🇯🇵", "👨‍👩‍👧"]);
+/// ```
+pub fn graphemes(s: &str) -> Graphemes<'_> {
+    Graphemes { rest: s }
+}
+
+impl<'a> Iterator for Graphemes<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        let mut chars = self.rest.char_indices();
+        let (_, first) = chars.next()?;
+        let mut prev = category(first);
+        // GB11の判定に使用する、Extended_Pictographic Extend* ZWJの並びの状態
+        let mut emoji = prev == Category::ExtendedPictographic;
+        let mut emoji_zwj = false;
+        // GB12及びGB13の判定に使用する、連続する地域指示記号の数
+        let mut regional_indicators = usize::from(prev == Category::RegionalIndicator);
+
+        let mut end = self.rest.len();
+        for (i, c) in chars {
+            let next = category(c);
+            if is_boundary(prev, next, emoji_zwj, regional_indicators) {
+                end = i;
+                break;
+            }
+
+            emoji_zwj = emoji && next == Category::Zwj;
+            emoji = match next {
+                Category::ExtendedPictographic => true,
+                Category::Extend => emoji,
+                _ => false,
+            };
+            regional_indicators = if next == Category::RegionalIndicator {
+                regional_indicators + 1
+            } else {
+                0
+            };
+            prev = next;
+        }
+
+        let (cluster, rest) = self.rest.split_at(end);
+        self.rest = rest;
+        Some(cluster)
+    }
+}
+
+/// 分類が`prev`と`next`の文字の間が、書記素クラスタの境界であるかを判定する。
+fn is_boundary(
+    prev: Category,
+    next: Category,
+    emoji_zwj: bool,
+    regional_indicators: usize,
+) -> bool {
+    use Category::*;
+
+    match (prev, next) {
+        // GB3
+        (CR, LF) => false,
+        // GB4及びGB5
+        (CR | LF | Control, _) | (_, CR | LF | Control) => true,
+        // GB6
+        (L, L | V | Lv | Lvt) => false,
+        // GB7
+        (Lv | V, V | T) => false,
+        // GB8
+        (Lvt | T, T) => false,
+        // GB9、GB9a及びGB9b
+        (_, Extend | Zwj | SpacingMark) | (Prepend, _) => false,
+        // GB11
+        (Zwj, ExtendedPictographic) if emoji_zwj => false,
+        // GB12及びGB13
+        (RegionalIndicator, RegionalIndicator) => regional_indicators.is_multiple_of(2),
+        // GB999
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hangul_syllables() {
+        // 結合済みの「한」と、字母を並べた「한」
+        let clusters: Vec<_> = graphemes("한\u{1112}\u{1161}\u{11AB}").collect();
+        assert_eq!(clusters, ["한", "\u{1112}\u{1161}\u{11AB}"]);
+    }
+
+    #[test]
+    fn regional_indicators_are_paired() {
+        let clusters: Vec<_> = graphemes("🇯🇵🇺🇸🇫").collect();
+        assert_eq!(clusters, ["🇯🇵", "🇺🇸", "🇫"]);
+    }
+
+    #[test]
+    fn crlf_is_one_cluster() {
+        let clusters: Vec<_> = graphemes("a\r\n\u{301}").collect();
+        assert_eq!(clusters, ["a", "\r\n", "\u{301}"]);
+    }
+
+    #[test]
+    fn emoji_modifier_and_zwj() {
+        let clusters: Vec<_> = graphemes("👍🏽a\u{200d}👍").collect();
+        assert_eq!(clusters, ["👍🏽", "a\u{200d}", "👍"]);
+    }
+}
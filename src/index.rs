@@ -0,0 +1,358 @@
+//! トークンごとに出現した位置を記録する転置索引を提供する。
+//!
+//! [`count`](../fn.count.html)は出現頻度だけを数えるが、[`InvertedIndex`](struct.InvertedIndex.html)は
+//! 各トークンが出現したファイル、行及び列を全て記録する。位置を記録する分だけメモリーを使用するため、
+//! 出現頻度だけが必要な場合は`count`を使用すること。
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::tokenizer::Tokenizer;
+use crate::{CountError, Frequencies};
+
+/// ファイルの先頭に記録する、形式の名前と版
+const HEADER: &str = "# wordcount index 1";
+
+/// トークンが出現した位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Position {
+    /// ファイルの番号。[`InvertedIndex::files`](struct.InvertedIndex.html#method.files)の添字。
+    pub file: usize,
+    /// 行番号（1から数える）
+    pub line: usize,
+    /// 行頭からのバイト数
+    pub offset: usize,
+    /// 行頭から数えた文字の位置（1から数える）
+    pub column: usize,
+}
+
+/// トークンから、出現した位置の一覧への対応（転置索引）
+///
+/// 位置はファイルを追加した順、ファイル中では出現した順に並ぶ。
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use kuroyasu_bicycle_book_wordcount::index::{InvertedIndex, Position};
+/// use kuroyasu_bicycle_book_wordcount::CountOption;
+///
+/// let mut index = InvertedIndex::new();
+/// index.add("a.txt", Cursor::new("foo bar\nbar"), CountOption::Word.tokenizer()).unwrap();
+/// index.add("b.txt", Cursor::new("ばー bar"), CountOption::Word.tokenizer()).unwrap();
+///
+/// let positions = index.positions("bar");
+/// assert_eq!(positions.len(), 3);
+/// assert_eq!(positions[0], Position { file: 0, line: 1, offset: 4, column: 5 });
+/// assert_eq!(positions[2], Position { file: 1, line: 1, offset: 7, column: 4 });
+/// assert_eq!(index.files()[positions[2].file], "b.txt");
+/// assert_eq!(index.frequencies()["bar"], 3);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct InvertedIndex {
+    files: Vec<String>,
+    postings: HashMap<String, Vec<Position>>,
+}
+
+impl InvertedIndex {
+    /// 空の索引を作成する。
+    pub fn new() -> Self {
+        InvertedIndex::default()
+    }
+
+    /// `name`という名前のファイルの内容`input`から、1行ずつ`tokenizer`でトークンを取り出して記録する。
+    ///
+    /// トークンの位置は[`concordance`](../concordance/index.html)と同様に求め、行の中に
+    /// 見つからないトークンは記録しない。正規化したトークンで記録したい場合は、
+    /// [`add_by`](#method.add_by)を使用すること。
+    ///
+    /// # Errors
+    ///
+    /// [`try_count`](../fn.try_count.html)と同じ条件でエラーを返す。エラーを返した場合は、
+    /// ファイルもそのトークンも記録しない。
+    pub fn add(
+        &mut self,
+        name: impl Into<String>,
+        input: impl BufRead,
+        tokenizer: impl Tokenizer,
+    ) -> Result<(), CountError> {
+        self.add_by(name, input, tokenizer, |token| Some(token.to_string()))
+    }
+
+    /// [`add`](#method.add)と同様に記録するが、トークンの代わりに`key`が返した文字列をキーとして記録する。
+    ///
+    /// `key`が`None`を返したトークンは記録しない。位置は、`tokenizer`が返したトークンの位置とする。
+    ///
+    /// # Errors
+    ///
+    /// [`add`](#method.add)と同じ条件でエラーを返す。
+    pub fn add_by(
+        &mut self,
+        name: impl Into<String>,
+        input: impl BufRead,
+        mut tokenizer: impl Tokenizer,
+        mut key: impl FnMut(&str) -> Option<String>,
+    ) -> Result<(), CountError> {
+        let file = self.files.len();
+        self.files.push(name.into());
+        let postings = &mut self.postings;
+        let mut line_no = 0;
+        let result = crate::for_each_line(input, |line| {
+            line_no += 1;
+            let mut from = 0;
+            // 直前に求めた位置から数えて、列を求める。
+            let (mut last_offset, mut last_column) = (0, 1);
+            tokenizer.tokenize(line, &mut |token| {
+                let span = match crate::concordance::locate(line, token, from) {
+                    Some(span) => span,
+                    None => return,
+                };
+                from = span.start + line[span.start..].chars().next().map_or(0, char::len_utf8);
+                if span.start < last_offset {
+                    (last_offset, last_column) = (0, 1);
+                }
+                last_column += line[last_offset..span.start].chars().count();
+                last_offset = span.start;
+                if let Some(key) = key(token) {
+                    postings.entry(key).or_default().push(Position {
+                        file,
+                        line: line_no,
+                        offset: span.start,
+                        column: last_column,
+                    });
+                }
+            });
+        });
+        if let Err(e) = result {
+            // このファイルの位置は、各トークンの位置の末尾にある。
+            self.postings.retain(|_, positions| {
+                while positions.last().is_some_and(|p| p.file == file) {
+                    positions.pop();
+                }
+                !positions.is_empty()
+            });
+            self.files.pop();
+            return Err(e);
+        }
+        // 入力の終わりに返されるトークンは、行に対応しないため記録しない。
+        tokenizer.finish(&mut |_| {});
+        Ok(())
+    }
+
+    /// 追加した順に、ファイルの名前を返す。
+    pub fn files(&self) -> &[String] {
+        &self.files
+    }
+
+    /// `key`が出現した位置を返す。出現していないトークンは空のスライスを返す。
+    pub fn positions(&self, key: &str) -> &[Position] {
+        self.postings.get(key).map_or(&[], Vec::as_slice)
+    }
+
+    /// 記録したトークンを、順不同で返すイテレーターを返す。
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.postings.keys().map(String::as_str)
+    }
+
+    /// トークンごとの出現頻度を返す。
+    pub fn frequencies(&self) -> Frequencies {
+        self.postings
+            .iter()
+            .map(|(key, positions)| (key.clone(), positions.len()))
+            .collect()
+    }
+
+    /// 記録した異なるトークンの数を返す。
+    pub fn len(&self) -> usize {
+        self.postings.len()
+    }
+
+    /// トークンを記録していなければ`true`を返す。
+    pub fn is_empty(&self) -> bool {
+        self.postings.is_empty()
+    }
+
+    /// 索引を、[`from_reader`](#method.from_reader)で読み込める形式で書き込む。
+    ///
+    /// 形式は、1行目が`# wordcount index 1`のUTF-8のテキストで、続けて次の行をタブ区切りで記録する。
+    /// トークンは辞書順に並べるため、同じ索引からは常に同じ内容が書き込まれる。
+    ///
+    /// - `F` ファイルの名前（ファイルの番号の順）
+    /// - `K` トークン 位置 … （位置は`ファイルの番号:行番号:バイト数:列`）
+    ///
+    /// 名前とトークン中の`\`、タブ、`\n`及び`\r`は、それぞれ`\\`、`\t`、`\n`及び`\r`と記録する。
+    ///
+    /// # Errors
+    ///
+    /// 書き込みに失敗した場合は、エラーを返す。
+    pub fn write_to(&self, mut writer: impl Write) -> io::Result<()> {
+        writeln!(writer, "{}", HEADER)?;
+        for name in &self.files {
+            writeln!(writer, "F\t{}", escape(name))?;
+        }
+        let mut keys: Vec<_> = self.postings.keys().collect();
+        keys.sort_unstable();
+        for key in keys {
+            write!(writer, "K\t{}", escape(key))?;
+            for p in &self.postings[key] {
+                write!(writer, "\t{}:{}:{}:{}", p.file, p.line, p.offset, p.column)?;
+            }
+            writeln!(writer)?;
+        }
+        writer.flush()
+    }
+
+    /// 索引を`path`のファイルに書き込む。形式は[`write_to`](#method.write_to)と同じ。
+    ///
+    /// # Errors
+    ///
+    /// ファイルの作成または書き込みに失敗した場合は、エラーを返す。
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.write_to(BufWriter::new(File::create(path)?))
+    }
+
+    /// [`write_to`](#method.write_to)で書き込んだ索引を読み込む。
+    ///
+    /// # Errors
+    ///
+    /// 読み込みに失敗した場合、または形式が正しくない場合は、エラーを返す。
+    pub fn from_reader(reader: impl BufRead) -> io::Result<Self> {
+        let mut lines = reader.lines();
+        if lines.next().transpose()?.as_deref() != Some(HEADER) {
+            return Err(invalid_data(1, "missing header"));
+        }
+        let mut index = InvertedIndex::new();
+        for (i, line) in lines.enumerate() {
+            let line_no = i + 2;
+            let line = line?;
+            let mut fields = line.split('\t');
+            match fields.next() {
+                Some("F") => {
+                    let name = fields
+                        .next()
+                        .ok_or_else(|| invalid_data(line_no, "no name"))?;
+                    index.files.push(
+                        unescape(name).ok_or_else(|| invalid_data(line_no, "invalid escape"))?,
+                    );
+                }
+                Some("K") => {
+                    let key = fields
+                        .next()
+                        .ok_or_else(|| invalid_data(line_no, "no key"))?;
+                    let key =
+                        unescape(key).ok_or_else(|| invalid_data(line_no, "invalid escape"))?;
+                    let positions = fields
+                        .map(|field| parse_position(field, index.files.len()))
+                        .collect::<Option<Vec<_>>>()
+                        .ok_or_else(|| invalid_data(line_no, "invalid position"))?;
+                    index.postings.insert(key, positions);
+                }
+                _ => return Err(invalid_data(line_no, "unknown record")),
+            }
+        }
+        Ok(index)
+    }
+
+    /// `path`のファイルから索引を読み込む。形式は[`write_to`](#method.write_to)と同じ。
+    ///
+    /// # Errors
+    ///
+    /// ファイルの読み込みに失敗した場合、または形式が正しくない場合は、エラーを返す。
+    pub fn from_file(path: impl AsRef<Path>) -> io::Result<Self> {
+        InvertedIndex::from_reader(BufReader::new(File::open(path)?))
+    }
+}
+
+/// `ファイルの番号:行番号:バイト数:列`の形式の位置を解析する。ファイルの番号は`files`未満とする。
+fn parse_position(field: &str, files: usize) -> Option<Position> {
+    let mut numbers = field.split(':').map(|n| n.parse::<usize>().ok());
+    let position = Position {
+        file: numbers.next()??,
+        line: numbers.next()??,
+        offset: numbers.next()??,
+        column: numbers.next()??,
+    };
+    if numbers.next().is_some() || position.file >= files {
+        return None;
+    }
+    Some(position)
+}
+
+fn invalid_data(line: usize, message: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("invalid index at line {}: {}", line, message),
+    )
+}
+
+/// `\`、タブ、`\n`及び`\r`をエスケープする。
+pub(crate) fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// [`escape`]でエスケープした文字列を元に戻す。エスケープが正しくない場合は`None`を返す。
+fn unescape(s: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        unescaped.push(match chars.next()? {
+            '\\' => '\\',
+            't' => '\t',
+            'n' => '\n',
+            'r' => '\r',
+            _ => return None,
+        });
+    }
+    Some(unescaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_round_trip() {
+        for s in ["", "plain", "a\tb\\c\nd\re", "\\t"] {
+            assert_eq!(unescape(&escape(s)).as_deref(), Some(s));
+        }
+        assert_eq!(unescape("bad\\x"), None);
+        assert_eq!(unescape("trailing\\"), None);
+    }
+
+    #[test]
+    fn invalid_positions() {
+        assert_eq!(
+            parse_position("0:1:2:3", 1),
+            Some(Position {
+                file: 0,
+                line: 1,
+                offset: 2,
+                column: 3
+            })
+        );
+        assert_eq!(parse_position("1:1:2:3", 1), None);
+        assert_eq!(parse_position("0:1:2", 1), None);
+        assert_eq!(parse_position("0:1:2:3:4", 1), None);
+        assert_eq!(parse_position("0:x:2:3", 1), None);
+    }
+}
@@ -0,0 +1,163 @@
+//! 2つのコーパスの出現頻度を比較し、一方で特に多く出現するトークン（特徴語）を求める機能を提供する。
+//!
+//! 対象コーパスと参照コーパスの出現頻度の2×2分割表から、対数尤度比（G²）、カイ二乗値及び
+//! 対数比を求める。
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use crate::Frequencies;
+
+/// 特徴語を並べる基準
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Measure {
+    /// 対数尤度比（G²）
+    #[default]
+    LogLikelihood,
+    /// カイ二乗値
+    ChiSquare,
+    /// 相対頻度の比の2を底とする対数
+    LogRatio,
+}
+
+/// トークンの出現頻度と、比較した結果
+///
+/// `log_likelihood`と`chi_square`は、対象コーパスでの相対頻度が参照コーパスより低い場合は
+/// 負の値とする。このため、どの基準でも値が大きいほど対象コーパスに特徴的なトークンである。
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Keyness<'a> {
+    /// トークン
+    pub key: &'a str,
+    /// 対象コーパスでの出現頻度
+    pub target: usize,
+    /// 参照コーパスでの出現頻度
+    pub reference: usize,
+    /// 対数尤度比（G²）
+    pub log_likelihood: f64,
+    /// イェーツの補正をしないカイ二乗値
+    pub chi_square: f64,
+    /// 相対頻度の比の2を底とする対数。出現頻度が`0`の場合は`0.5`とみなして求める。
+    pub log_ratio: f64,
+}
+
+impl Keyness<'_> {
+    /// `measure`で指定された基準の値を返す。
+    pub fn score(&self, measure: Measure) -> f64 {
+        match measure {
+            Measure::LogLikelihood => self.log_likelihood,
+            Measure::ChiSquare => self.chi_square,
+            Measure::LogRatio => self.log_ratio,
+        }
+    }
+}
+
+/// `target`と`reference`の少なくとも一方に出現する全てのトークンを、`measure`の値が大きい順に返す。
+///
+/// 値が同じトークンは辞書順に並べる。
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use kuroyasu_bicycle_book_wordcount::keyness::{compare, Measure};
+/// use kuroyasu_bicycle_book_wordcount::{count, CountOption, Frequencies};
+///
+/// let target = Frequencies::from(count(Cursor::new("rust is fast and rust is safe"), CountOption::Word));
+/// let reference = Frequencies::from(count(Cursor::new("python is easy and python is popular"), CountOption::Word));
+/// let keywords = compare(&target, &reference, Measure::LogLikelihood);
+/// assert_eq!(keywords[0].key, "rust");
+/// assert_eq!(keywords.last().unwrap().key, "python");
+/// ```
+pub fn compare<'a>(
+    target: &'a Frequencies,
+    reference: &'a Frequencies,
+    measure: Measure,
+) -> Vec<Keyness<'a>> {
+    let n1 = target.total() as f64;
+    let n2 = reference.total() as f64;
+    let mut keywords: Vec<_> = target
+        .iter()
+        .chain(reference.iter().filter(|(key, _)| target.get(key) == 0))
+        .map(|(key, _)| {
+            let a = target.get(key);
+            let b = reference.get(key);
+            let (x, y) = (a as f64, b as f64);
+            // 対象コーパスでの相対頻度が低ければ、G²とカイ二乗値を負にする。
+            let sign = if x * n2 < y * n1 { -1.0 } else { 1.0 };
+            Keyness {
+                key,
+                target: a,
+                reference: b,
+                log_likelihood: sign * log_likelihood(x, y, n1, n2),
+                chi_square: sign * chi_square(x, y, n1, n2),
+                log_ratio: log_ratio(x, y, n1, n2),
+            }
+        })
+        .collect();
+    keywords.sort_by(|a, b| {
+        b.score(measure)
+            .total_cmp(&a.score(measure))
+            .then_with(|| a.key.cmp(b.key))
+    });
+    keywords
+}
+
+/// 対象コーパスの出現頻度`a`と延べ数`n1`、参照コーパスの出現頻度`b`と延べ数`n2`から、G²を求める。
+fn log_likelihood(a: f64, b: f64, n1: f64, n2: f64) -> f64 {
+    let n = n1 + n2;
+    let e1 = n1 * (a + b) / n;
+    let e2 = n2 * (a + b) / n;
+    let term = |o: f64, e: f64| if o > 0.0 { o * (o / e).ln() } else { 0.0 };
+    2.0 * (term(a, e1) + term(b, e2))
+}
+
+/// 2×2分割表のカイ二乗値を求める。引数は[`log_likelihood`]と同じ。
+fn chi_square(a: f64, b: f64, n1: f64, n2: f64) -> f64 {
+    let (c, d) = (n1 - a, n2 - b);
+    let denominator = (a + b) * (c + d) * n1 * n2;
+    if denominator == 0.0 {
+        return 0.0;
+    }
+    let diff = a * d - b * c;
+    (n1 + n2) * diff * diff / denominator
+}
+
+/// 相対頻度の比の対数を求める。引数は[`log_likelihood`]と同じ。
+fn log_ratio(a: f64, b: f64, n1: f64, n2: f64) -> f64 {
+    if n1 == 0.0 || n2 == 0.0 {
+        return 0.0;
+    }
+    let a = if a > 0.0 { a } else { 0.5 };
+    let b = if b > 0.0 { b } else { 0.5 };
+    ((a / n1) / (b / n2)).log2()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-6,
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn known_values() {
+        // a = 10, b = 5, n1 = 1000, n2 = 2000
+        assert_close(log_likelihood(10.0, 5.0, 1000.0, 2000.0), 6.931472);
+        assert_close(chi_square(10.0, 5.0, 1000.0, 2000.0), 7.537688);
+        assert_close(log_ratio(10.0, 5.0, 1000.0, 2000.0), 2.0);
+        assert_close(log_ratio(0.0, 1.0, 100.0, 100.0), -1.0);
+    }
+
+    #[test]
+    fn empty_corpus() {
+        assert_eq!(log_likelihood(1.0, 0.0, 1.0, 0.0), 0.0);
+        assert_eq!(chi_square(1.0, 0.0, 1.0, 0.0), 0.0);
+        assert_eq!(log_ratio(1.0, 0.0, 1.0, 0.0), 0.0);
+    }
+}
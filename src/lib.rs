@@ -1,19 +1,109 @@
 //! `wordcount`は、シンプルな文字、単語または行の出現頻度を数える機能を提供する。
 //! なお、行は行数ではなく、その行で記録されている文字列が一致する行の数を数える。
 //! 詳しくは、[`count`](fn.count.html)関数のドキュメントを参照すること。
-use regex::Regex;
 use std::collections::HashMap;
 use std::io::BufRead;
 
+mod bytes;
+pub mod cardinality;
+pub mod collocation;
+pub mod concordance;
+mod counter;
+pub mod encoding;
+mod error;
+mod frequencies;
+pub mod grapheme;
+pub mod index;
+pub mod keyness;
+pub mod morph;
+mod ngram;
+pub mod normalize;
+pub mod output;
+pub mod parallel;
+mod pattern;
+pub mod stats;
+pub mod stem;
+pub mod stopwords;
+pub mod tfidf;
+pub mod tokenizer;
+pub mod topk;
+
+pub use crate::bytes::{count_byte_ngrams, count_bytes, try_count_byte_ngrams, try_count_bytes};
+pub use crate::counter::Counter;
+pub use crate::error::CountError;
+pub use crate::frequencies::{Frequencies, FrequencyIter};
+pub use crate::pattern::{Pattern, PatternError};
+use crate::tokenizer::{
+    ByteTokenizer, CharTokenizer, GraphemeTokenizer, LineTokenizer, MorphemeTokenizer,
+    NgramTokenizer, StemTokenizer, Tokenizer, WordTokenizer,
+};
+
 /// [`count`](fn.count.html)で使用するオプション
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum CountOption {
     /// 文字の出現頻度を数える。
     Char,
+    /// 拡張書記素クラスタ（利用者が1文字として認識する単位）の出現頻度を数える。
+    Grapheme,
     /// 単語の出現頻度を数える。
     Word,
+    /// 英単語を語幹に変換して、語幹の出現頻度を数える。
+    ///
+    /// `running`、`runs`及び`ran`は、いずれも語幹`run`として数える。
+    Stem,
+    /// 日本語の文を形態素解析して、形態素の出現頻度を数える。
+    Morpheme(morph::MorphemeOption),
     /// 行の出現頻度を数える。
     Line,
+    /// `option`に従って空白や大文字と小文字の違いを整えた行の出現頻度を数える。
+    LineWith(tokenizer::LineOption),
+    /// 連続する`n`文字（文字n-gram）の出現頻度を数える。
+    ///
+    /// `cross_lines`が`true`の場合、行をまたぐn-gramも数える。
+    CharNgram { n: usize, cross_lines: bool },
+    /// 連続する`n`単語（単語n-gram）の出現頻度を数える。単語は空白1つで連結する。
+    ///
+    /// `cross_lines`が`true`の場合、行をまたぐn-gramも数える。
+    WordNgram { n: usize, cross_lines: bool },
+    /// 入力を文字列としてデコードせずに、バイトの出現頻度を数える。
+    ///
+    /// キーは`0a`のような小文字の16進数の文字列である。
+    Byte,
+    /// 入力を文字列としてデコードせずに、連続する`n`バイト（バイトn-gram）の出現頻度を数える。
+    ///
+    /// キーは`cafe`のように、各バイトを区切らずに並べた小文字の16進数の文字列である。
+    ByteNgram { n: usize },
+}
+
+impl CountOption {
+    /// オプションに対応する組み込みのトークナイザーを返す。
+    ///
+    /// トークナイザーはデコード済みの行を受け取るため、[`Byte`](#variant.Byte)と
+    /// [`ByteNgram`](#variant.ByteNgram)のトークナイザーは、行末の改行を除いた各行のUTF-8の
+    /// バイトを数える。改行を含む入力の全てのバイトを数える場合は、[`count`](fn.count.html)を使用すること。
+    pub fn tokenizer(self) -> Box<dyn Tokenizer + Send> {
+        match self {
+            CountOption::Char => Box::new(CharTokenizer),
+            CountOption::Grapheme => Box::new(GraphemeTokenizer),
+            CountOption::Word => Box::new(WordTokenizer),
+            CountOption::Stem => Box::new(StemTokenizer::new(WordTokenizer)),
+            CountOption::Morpheme(option) => {
+                Box::new(MorphemeTokenizer::new(morph::Dictionary::bundled(), option))
+            }
+            CountOption::Line => Box::new(LineTokenizer::default()),
+            CountOption::LineWith(option) => Box::new(LineTokenizer::new(option)),
+            CountOption::CharNgram { n, cross_lines } => {
+                Box::new(NgramTokenizer::new(CharTokenizer, n, "", cross_lines))
+            }
+            CountOption::WordNgram { n, cross_lines } => {
+                Box::new(NgramTokenizer::new(WordTokenizer, n, " ", cross_lines))
+            }
+            CountOption::Byte => Box::new(ByteTokenizer),
+            CountOption::ByteNgram { n } => {
+                Box::new(NgramTokenizer::new(ByteTokenizer, n, "", false))
+            }
+        }
+    }
 }
 
 /// オプションのデフォルトは、[`word`](enum.CountOption.html#variant.Word)。
@@ -27,12 +117,26 @@ impl Default for CountOption {
 ///
 /// 頻度を数える対象は、オプションによって制御される。
 /// * [`CountOption::Char`](enum.CountOption.html#variant.Char): Unicodeの1文字ごと。
+/// * [`CountOption::Grapheme`](enum.CountOption.html#variant.Grapheme): UAX #29に従った拡張書記素クラスタごと。
 /// * [`CountOption::Word`](enum.CountOption.html#variant.Word): 正規表現`\w+`にマッチする単語ごと。
+/// * [`CountOption::Stem`](enum.CountOption.html#variant.Stem): 単語を語幹に変換した語幹ごと。
+/// * [`CountOption::Morpheme`](enum.CountOption.html#variant.Morpheme): 同梱の辞書で形態素解析した形態素ごと。
 /// * [`CountOption::Line`](enum.CountOption.html#variant.Line): `\n`または`\r\n`で区切られた1行ごと。
+/// * [`CountOption::LineWith`](enum.CountOption.html#variant.LineWith): 空白などを整えた1行ごと。
+/// * [`CountOption::CharNgram`](enum.CountOption.html#variant.CharNgram): 連続する`n`文字ごと。
+/// * [`CountOption::WordNgram`](enum.CountOption.html#variant.WordNgram): 連続する`n`単語ごと。
+/// * [`CountOption::Byte`](enum.CountOption.html#variant.Byte): デコードしない入力の1バイトごと。
+/// * [`CountOption::ByteNgram`](enum.CountOption.html#variant.ByteNgram): デコードしない入力の連続する`n`バイトごと。
+///
+/// n-gramの`n`が`0`の場合は、何も数えない。
+/// 入力の先頭にUTF-8のBOMがあれば、数えずに読み飛ばす。
+/// バイトのモードは入力をUTF-8文字列として扱わないため、任意のバイナリーを数えられる。
+/// このため、バイトのモードではBOMも数える。
 ///
 /// # Panics
 ///
-/// 入力がUTF-8文字列でない場合は、パニックを起こす。
+/// 入力の読み込みに失敗した場合、または入力がUTF-8文字列でない場合は、パニックを起こす。
+/// パニックを避けたい場合は、[`try_count`](fn.try_count.html)を使用すること。
 /// 
 /// # Examples
 /// 
@@ -49,28 +153,450 @@ impl Default for CountOption {
 /// assert_eq!(freqs["bb"], 2);
 /// assert_eq!(freqs["cc"], 1);
 pub fn count(input: impl BufRead, option: CountOption) -> HashMap<String, usize> {
-    let re = Regex::new(r"\w+").unwrap();
-    let mut freqs = HashMap::new();
+    try_count(input, option).unwrap_or_else(|e| panic!("{}", e))
+}
 
-    for line in input.lines() {
-        let line = line.unwrap();
-        match option {
-            CountOption::Char => {
-                for c in line.chars() {
-                    *freqs.entry(c.to_string()).or_insert(0) += 1;
-                }
-            }
-            CountOption::Word => {
-                for m in re.find_iter(&line) {
-                    let word = m.as_str().to_string();
-                    *freqs.entry(word).or_insert(0) += 1;
-                }
+/// [`count`](fn.count.html)と同様に出現頻度を数えるが、パニックを起こす代わりにエラーを返す。
+///
+/// # Errors
+///
+/// 入力の読み込みに失敗した場合は[`CountError::Io`](enum.CountError.html#variant.Io)を、
+/// 入力がUTF-8文字列でない場合は[`CountError::Decode`](enum.CountError.html#variant.Decode)を返す。
+/// 入力が[`encoding::Decoder`](encoding/struct.Decoder.html)で、変換に失敗した場合も
+/// [`CountError::Decode`](enum.CountError.html#variant.Decode)を返す。
+/// どちらのエラーも、問題が発生した行番号とバイト位置を保持している。
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use kuroyasu_bicycle_book_wordcount::{try_count, CountError, CountOption};
+///
+/// let input = Cursor::new(b"aa\nbb \xff\n");
+/// match try_count(input, CountOption::Word) {
+///     Err(CountError::Decode { line, offset }) => {
+///         assert_eq!(line, 2);
+///         assert_eq!(offset, 6);
+///     }
+///     _ => unreachable!(),
+/// }
+/// ```
+pub fn try_count(
+    input: impl BufRead,
+    option: CountOption,
+) -> Result<HashMap<String, usize>, CountError> {
+    match option {
+        // 文字は種類が少ないため、`char`をキーにして数え、最後に文字列に変換する。
+        CountOption::Char => try_count_chars(input).map(|freqs| {
+            freqs
+                .into_iter()
+                .map(|(c, count)| (c.to_string(), count))
+                .collect()
+        }),
+        // バイトは行に分割せず、入力をそのまま数える。
+        CountOption::Byte => try_count_bytes(input).map(|histogram| {
+            (0..=u8::MAX)
+                .zip(histogram)
+                .filter(|&(_, count)| count > 0)
+                .map(|(b, count)| (bytes::hex(&[b]), count))
+                .collect()
+        }),
+        CountOption::ByteNgram { n } => try_count_byte_ngrams(input, n).map(|freqs| {
+            freqs
+                .into_iter()
+                .map(|(ngram, count)| (bytes::hex(&ngram), count))
+                .collect()
+        }),
+        _ => try_count_with(input, option.tokenizer()),
+    }
+}
+
+/// `input`中の文字の出現頻度を、`char`をキーにして数える。
+///
+/// [`count`](fn.count.html)を[`CountOption::Char`](enum.CountOption.html#variant.Char)で
+/// 呼び出した場合と同じ出現頻度を数えるが、キーの文字列を割り当てない。
+///
+/// # Panics
+///
+/// 入力の読み込みに失敗した場合、または入力がUTF-8文字列でない場合は、パニックを起こす。
+/// パニックを避けたい場合は、[`try_count_chars`](fn.try_count_chars.html)を使用すること。
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use kuroyasu_bicycle_book_wordcount::count_chars;
+///
+/// let freqs = count_chars(Cursor::new("abあa"));
+/// assert_eq!(freqs[&'a'], 2);
+/// assert_eq!(freqs[&'あ'], 1);
+/// ```
+pub fn count_chars(input: impl BufRead) -> HashMap<char, usize> {
+    try_count_chars(input).unwrap_or_else(|e| panic!("{}", e))
+}
+
+/// [`count_chars`](fn.count_chars.html)と同様に出現頻度を数えるが、パニックを起こす代わりにエラーを返す。
+///
+/// # Errors
+///
+/// [`try_count`](fn.try_count.html)と同じ条件でエラーを返す。
+pub fn try_count_chars(input: impl BufRead) -> Result<HashMap<char, usize>, CountError> {
+    // ASCII文字は配列で数え、最後にマップに移す。
+    let mut ascii = [0; 128];
+    let mut freqs = HashMap::new();
+    for_each_line(input, |line| {
+        for c in line.chars() {
+            if c.is_ascii() {
+                ascii[c as usize] += 1;
+            } else {
+                *freqs.entry(c).or_insert(0) += 1;
             }
-            CountOption::Line => *freqs.entry(line.to_string()).or_insert(0) += 1,
+        }
+    })?;
+    let ascii = ascii.iter().enumerate().filter(|(_, &count)| count > 0);
+    freqs.extend(ascii.map(|(b, &count)| (char::from(b as u8), count)));
+    Ok(freqs)
+}
+
+/// `tokenizer`が取り出したトークンの出現頻度を数える。
+///
+/// 独自の[`Tokenizer`](tokenizer/trait.Tokenizer.html)を実装すれば、組み込みのモード以外の
+/// 方法で分割したトークンを数えられる。
+///
+/// # Panics
+///
+/// 入力の読み込みに失敗した場合、または入力がUTF-8文字列でない場合は、パニックを起こす。
+/// パニックを避けたい場合は、[`try_count_with`](fn.try_count_with.html)を使用すること。
+pub fn count_with(input: impl BufRead, tokenizer: impl Tokenizer) -> HashMap<String, usize> {
+    try_count_with(input, tokenizer).unwrap_or_else(|e| panic!("{}", e))
+}
+
+/// [`count_with`](fn.count_with.html)と同様に出現頻度を数えるが、パニックを起こす代わりにエラーを返す。
+///
+/// # Errors
+///
+/// [`try_count`](fn.try_count.html)と同じ条件でエラーを返す。
+pub fn try_count_with(
+    input: impl BufRead,
+    mut tokenizer: impl Tokenizer,
+) -> Result<HashMap<String, usize>, CountError> {
+    let mut freqs = HashMap::new();
+    let mut emit = |token: &str| increment(&mut freqs, token);
+    for_each_line(input, |line| tokenizer.tokenize(line, &mut emit))?;
+    tokenizer.finish(&mut emit);
+    Ok(freqs)
+}
+
+/// 正規表現のパターンにマッチしたトークンの出現頻度を数える。
+///
+/// # Panics
+///
+/// 入力の読み込みに失敗した場合、または入力がUTF-8文字列でない場合は、パニックを起こす。
+/// パニックを避けたい場合は、[`try_count_pattern`](fn.try_count_pattern.html)を使用すること。
+///
+/// # Examples
+///
+/// 単語の中のアポストロフィやハイフンを含めて数える例。
+///
+/// ```
+/// use std::io::Cursor;
+/// use kuroyasu_bicycle_book_wordcount::{count_pattern, Pattern};
+///
+/// let pattern = Pattern::new(r"\w+(?:['-]\w+)*").unwrap();
+/// let freqs = count_pattern(Cursor::new("don't use state-of-the-art don't"), &pattern);
+/// assert_eq!(freqs["don't"], 2);
+/// assert_eq!(freqs["state-of-the-art"], 1);
+/// ```
+pub fn count_pattern(input: impl BufRead, pattern: &Pattern) -> HashMap<String, usize> {
+    try_count_pattern(input, pattern).unwrap_or_else(|e| panic!("{}", e))
+}
+
+/// [`count_pattern`](fn.count_pattern.html)と同様に出現頻度を数えるが、パニックを起こす代わりにエラーを返す。
+///
+/// # Errors
+///
+/// [`try_count`](fn.try_count.html)と同じ条件でエラーを返す。
+pub fn try_count_pattern(
+    input: impl BufRead,
+    pattern: &Pattern,
+) -> Result<HashMap<String, usize>, CountError> {
+    try_count_with(input, pattern.clone())
+}
+
+/// `tokenizer`が取り出した英単語を語幹に変換し、語幹ごとの出現頻度と語形ごとの出現頻度を数える。
+///
+/// 語幹だけを数える場合は[`CountOption::Stem`](enum.CountOption.html#variant.Stem)で十分だが、
+/// 語幹は`happi`のように読みにくいことがあるため、この関数で語幹ごとに最も出現頻度が高い語形を
+/// 求めて表示に使用できる。
+///
+/// # Panics
+///
+/// 入力の読み込みに失敗した場合、または入力がUTF-8文字列でない場合は、パニックを起こす。
+/// パニックを避けたい場合は、[`try_count_stems`](fn.try_count_stems.html)を使用すること。
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use kuroyasu_bicycle_book_wordcount::count_stems;
+/// use kuroyasu_bicycle_book_wordcount::tokenizer::WordTokenizer;
+///
+/// let input = Cursor::new("running runs ran\nrunning");
+/// let stems = count_stems(input, WordTokenizer);
+/// assert_eq!(stems["run"].count(), 4);
+/// assert_eq!(stems["run"].most_frequent_surface(), "running");
+/// ```
+pub fn count_stems(
+    input: impl BufRead,
+    tokenizer: impl Tokenizer,
+) -> HashMap<String, stem::StemCount> {
+    try_count_stems(input, tokenizer).unwrap_or_else(|e| panic!("{}", e))
+}
+
+/// [`count_stems`](fn.count_stems.html)と同様に出現頻度を数えるが、パニックを起こす代わりにエラーを返す。
+///
+/// # Errors
+///
+/// [`try_count`](fn.try_count.html)と同じ条件でエラーを返す。
+pub fn try_count_stems(
+    input: impl BufRead,
+    mut tokenizer: impl Tokenizer,
+) -> Result<HashMap<String, stem::StemCount>, CountError> {
+    let mut stems: HashMap<String, stem::StemCount> = HashMap::new();
+    let mut buf = String::new();
+    let mut emit = |token: &str| {
+        stem::stem_into(token, &mut buf);
+        match stems.get_mut(&buf) {
+            Some(count) => count.add(token),
+            None => stems.entry(buf.clone()).or_default().add(token),
+        }
+    };
+    for_each_line(input, |line| tokenizer.tokenize(line, &mut emit))?;
+    tokenizer.finish(&mut emit);
+    Ok(stems)
+}
+
+/// `tokenizer`が取り出したトークンのうち、出現頻度が高い`k`個のトークンを、おおむね`memory`バイトの
+/// メモリーで近似的に数える。
+///
+/// ほとんどのトークンが一度しか出現しない入力でも、使用するメモリーは増え続けない。
+/// 数えた出現頻度の誤差については、[`topk::TopK`](topk/struct.TopK.html)を参照すること。
+///
+/// # Panics
+///
+/// 入力の読み込みに失敗した場合、または入力がUTF-8文字列でない場合は、パニックを起こす。
+/// パニックを避けたい場合は、[`try_count_top`](fn.try_count_top.html)を使用すること。
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use kuroyasu_bicycle_book_wordcount::{count_top, CountOption};
+///
+/// let input = Cursor::new("GET /\nGET /about\nGET /\n");
+/// let top = count_top(input, CountOption::Line.tokenizer(), 1, 64 * 1024);
+/// assert_eq!(top.top()[0].key, "GET /");
+/// assert_eq!(top.top()[0].count, 2);
+/// ```
+pub fn count_top(
+    input: impl BufRead,
+    tokenizer: impl Tokenizer,
+    k: usize,
+    memory: usize,
+) -> topk::TopK {
+    try_count_top(input, tokenizer, k, memory).unwrap_or_else(|e| panic!("{}", e))
+}
+
+/// [`count_top`](fn.count_top.html)と同様に出現頻度を数えるが、パニックを起こす代わりにエラーを返す。
+///
+/// # Errors
+///
+/// [`try_count`](fn.try_count.html)と同じ条件でエラーを返す。
+pub fn try_count_top(
+    input: impl BufRead,
+    mut tokenizer: impl Tokenizer,
+    k: usize,
+    memory: usize,
+) -> Result<topk::TopK, CountError> {
+    let mut top = topk::TopK::new(k, memory);
+    let mut emit = |token: &str| top.add(token);
+    for_each_line(input, |line| tokenizer.tokenize(line, &mut emit))?;
+    tokenizer.finish(&mut emit);
+    Ok(top)
+}
+
+/// `tokenizer`が取り出した異なるトークンの数を、精度が`precision`のHyperLogLogで推定する。
+///
+/// 出現頻度を記録しないため、異なるトークンが多い入力でも少ないメモリーで数えられる。
+///
+/// # Panics
+///
+/// 入力の読み込みに失敗した場合、または入力がUTF-8文字列でない場合は、パニックを起こす。
+/// パニックを避けたい場合は、[`try_count_distinct`](fn.try_count_distinct.html)を使用すること。
+/// `precision`が範囲外の場合も、[`HyperLogLog::new`](cardinality/struct.HyperLogLog.html#method.new)
+/// と同様にパニックを起こす。
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use kuroyasu_bicycle_book_wordcount::{count_distinct, CountOption};
+///
+/// let input = Cursor::new("aa bb cc bb aa");
+/// let hll = count_distinct(input, CountOption::Word.tokenizer(), 10);
+/// assert_eq!(hll.estimate().round(), 3.0);
+/// ```
+pub fn count_distinct(
+    input: impl BufRead,
+    tokenizer: impl Tokenizer,
+    precision: u8,
+) -> cardinality::HyperLogLog {
+    try_count_distinct(input, tokenizer, precision).unwrap_or_else(|e| panic!("{}", e))
+}
+
+/// [`count_distinct`](fn.count_distinct.html)と同様に推定するが、入力に問題がある場合はパニックを
+/// 起こす代わりにエラーを返す。
+///
+/// # Errors
+///
+/// [`try_count`](fn.try_count.html)と同じ条件でエラーを返す。
+pub fn try_count_distinct(
+    input: impl BufRead,
+    mut tokenizer: impl Tokenizer,
+    precision: u8,
+) -> Result<cardinality::HyperLogLog, CountError> {
+    let mut hll = cardinality::HyperLogLog::new(precision);
+    let mut emit = |token: &str| hll.add(token);
+    for_each_line(input, |line| tokenizer.tokenize(line, &mut emit))?;
+    tokenizer.finish(&mut emit);
+    Ok(hll)
+}
+
+/// `input`から1行ずつ`tokenizer`でトークンを取り出し、同じ行で隣り合うトークンの組を数える。
+///
+/// # Panics
+///
+/// 入力の読み込みに失敗した場合、または入力がUTF-8文字列でない場合は、パニックを起こす。
+/// パニックを避けたい場合は、[`try_count_collocations`](fn.try_count_collocations.html)を使用すること。
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use kuroyasu_bicycle_book_wordcount::collocation::Association;
+/// use kuroyasu_bicycle_book_wordcount::{count_collocations, CountOption};
+///
+/// let input = Cursor::new("machine learning\nlearning machine learning");
+/// let collocations = count_collocations(input, CountOption::Word.tokenizer());
+/// assert_eq!(collocations.count("machine", "learning"), 2);
+/// // 行をまたぐ`learning`と`learning`の組は数えない。
+/// assert_eq!(collocations.total(), 3);
+/// assert_eq!(collocations.rank(Association::TScore, 2)[0].first, "machine");
+/// ```
+pub fn count_collocations(
+    input: impl BufRead,
+    tokenizer: impl Tokenizer,
+) -> collocation::Collocations {
+    try_count_collocations(input, tokenizer).unwrap_or_else(|e| panic!("{}", e))
+}
+
+/// [`count_collocations`](fn.count_collocations.html)と同様に数えるが、入力に問題がある場合は
+/// パニックを起こす代わりにエラーを返す。
+///
+/// # Errors
+///
+/// [`try_count`](fn.try_count.html)と同じ条件でエラーを返す。
+pub fn try_count_collocations(
+    input: impl BufRead,
+    mut tokenizer: impl Tokenizer,
+) -> Result<collocation::Collocations, CountError> {
+    let mut collocations = collocation::Collocations::new();
+    let mut previous = None;
+    for_each_line(input, |line| {
+        tokenizer.tokenize(line, &mut |token| {
+            add_pair(&mut collocations, &mut previous, token)
+        });
+        previous = None;
+    })?;
+    tokenizer.finish(&mut |token| add_pair(&mut collocations, &mut previous, token));
+    Ok(collocations)
+}
+
+/// 同じ行の直前のトークン`previous`があれば、`token`との組を数え、`previous`を`token`に置き換える。
+fn add_pair(
+    collocations: &mut collocation::Collocations,
+    previous: &mut Option<String>,
+    token: &str,
+) {
+    match previous {
+        Some(previous) => {
+            collocations.add(previous, token);
+            previous.clear();
+            previous.push_str(token);
+        }
+        None => *previous = Some(token.to_string()),
+    }
+}
+
+/// `token`の出現頻度を1つ増やす。
+///
+/// 既に数えているトークンは`&str`のまま検索し、新しいトークンだけキーの文字列を割り当てる。
+pub(crate) fn increment(freqs: &mut HashMap<String, usize>, token: &str) {
+    match freqs.get_mut(token) {
+        Some(count) => *count += 1,
+        None => {
+            freqs.insert(token.to_string(), 1);
         }
     }
+}
+
+/// `input`から1行ずつUTF-8文字列を読み込み、行末の改行を取り除いて`f`に渡す。
+fn for_each_line(mut input: impl BufRead, mut f: impl FnMut(&str)) -> Result<(), CountError> {
+    let mut buf = Vec::new();
+    let mut line_no = 0;
+    let mut offset = 0;
+
+    loop {
+        buf.clear();
+        line_no += 1;
+        match input.read_until(b'\n', &mut buf) {
+            Ok(0) => return Ok(()),
+            Ok(_) => {}
+            Err(source) => {
+                let decode_error = source
+                    .get_ref()
+                    .and_then(|e| e.downcast_ref::<encoding::DecodeError>());
+                return Err(match decode_error {
+                    // `encoding::Decoder`が変換に失敗した場合は、元の入力での位置を報告する。
+                    Some(e) => CountError::Decode {
+                        line: line_no,
+                        offset: e.offset(),
+                    },
+                    None => CountError::Io {
+                        line: line_no,
+                        offset: offset + buf.len(),
+                        source,
+                    },
+                });
+            }
+        }
+        let line = std::str::from_utf8(&buf).map_err(|e| CountError::Decode {
+            line: line_no,
+            offset: offset + e.valid_up_to(),
+        })?;
+        offset += buf.len();
+        // 先頭のBOMは、ファイルを分担して数える場合と同様に読み飛ばす。
+        let line = match line_no {
+            1 => line.strip_prefix('\u{feff}').unwrap_or(line),
+            _ => line,
+        };
+        // `BufRead::lines`と同様に、行末の`\n`または`\r\n`を取り除く。
+        let line = line
+            .strip_suffix('\n')
+            .map_or(line, |l| l.strip_suffix('\r').unwrap_or(l));
 
-    freqs
+        f(line);
+    }
 }
 
 #[cfg(test)]
@@ -140,6 +666,45 @@ mod tests {
         );
     }
 
+    #[test]
+    fn try_count_reports_decode_error() {
+        use std::io::Cursor;
+        let err = try_count(
+            Cursor::new([
+                b'a', b'\n', // a
+                b'b',  // b
+                0xf0, 0x90, 0x80, // 出鱈目なバイト列
+                0xe3, 0x81, 0x82, // あ
+            ]),
+            CountOption::Word,
+        )
+        .unwrap_err();
+        assert!(matches!(err, CountError::Decode { line: 2, offset: 3 }));
+    }
+
+    #[test]
+    fn try_count_reports_io_error() {
+        use std::io::{BufReader, Read};
+
+        // 4バイト目以降の読み込みに失敗する入力
+        struct Broken(usize);
+        impl Read for Broken {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                if self.0 >= 3 {
+                    return Err(io::Error::other("broken"));
+                }
+                buf[0] = b"a\nb"[self.0];
+                self.0 += 1;
+                Ok(1)
+            }
+        }
+
+        let err = try_count(BufReader::with_capacity(1, Broken(0)), CountOption::Line).unwrap_err();
+        assert_eq!(err.line(), 2);
+        assert_eq!(err.offset(), 3);
+        assert!(matches!(err, CountError::Io { .. }));
+    }
+
     #[test]
     #[ignore]
     fn large_test() {
@@ -1,17 +1,1038 @@
+use std::collections::HashMap;
 use std::env;
-use std::fs::File;
-use std::io::BufReader;
+use std::fs::{self, File};
+use std::io::{self, BufReader};
+use std::path::Path;
+use std::process;
 
-use kuroyasu_bicycle_book_wordcount::count;
+use kuroyasu_bicycle_book_wordcount::cardinality::{
+    DEFAULT_PRECISION, MAX_PRECISION, MIN_PRECISION,
+};
+use kuroyasu_bicycle_book_wordcount::collocation::Association;
+use kuroyasu_bicycle_book_wordcount::concordance::{display_width, try_find_by, Context};
+use kuroyasu_bicycle_book_wordcount::encoding::{Decoder, Encoding};
+use kuroyasu_bicycle_book_wordcount::index::InvertedIndex;
+use kuroyasu_bicycle_book_wordcount::keyness::{compare, Measure};
+use kuroyasu_bicycle_book_wordcount::morph::{Dictionary, MorphemeOption, PosSet};
+use kuroyasu_bicycle_book_wordcount::normalize::{Kana, Normalizer, UnicodeForm};
+use kuroyasu_bicycle_book_wordcount::output::{Format, Table, Value};
+use kuroyasu_bicycle_book_wordcount::parallel::{
+    effective_jobs, try_count_file, try_count_file_with,
+};
+use kuroyasu_bicycle_book_wordcount::stats::Statistics;
+use kuroyasu_bicycle_book_wordcount::stem::stem;
+use kuroyasu_bicycle_book_wordcount::stopwords::Stopwords;
+use kuroyasu_bicycle_book_wordcount::tfidf::{Bm25, Corpus};
+use kuroyasu_bicycle_book_wordcount::tokenizer::{
+    LineOption, MorphemeTokenizer, NgramTokenizer, NormalizedTokenizer, StemTokenizer,
+    StopwordTokenizer, Tokenizer,
+};
+use kuroyasu_bicycle_book_wordcount::{
+    try_count_collocations, try_count_distinct, try_count_stems, try_count_top, try_count_with,
+    CountError, CountOption, Frequencies, Pattern,
+};
+
+const USAGE: &str = "\
+usage: wordcount [OPTIONS] FILENAME
+       wordcount compare [OPTIONS] TARGET REFERENCE
+       wordcount tfidf [OPTIONS] PATH...
+       wordcount collocations [OPTIONS] FILENAME
+       wordcount kwic [OPTIONS] KEYWORD PATH...
+       wordcount index [OPTIONS] PATH...
+
+commands:
+    compare           TARGETに特徴的なトークンを、REFERENCEと比較して出現頻度から求める
+    tfidf             ファイルごとに特徴的なトークンを、TF-IDFで求める。ディレクトリーを指定した場合は、
+                      その中の全てのファイルを1つずつ文書として扱う
+    collocations      同じ行で隣り合うトークンの組を数え、結びつきが強い順に表示する
+    kwic              KEYWORDの全ての出現箇所を、前後の文脈とともにファイル名と行番号を付けて表示する
+    index             トークンごとに全ての出現箇所（ファイル、行、バイト数、列）を記録した索引を出力する
+
+options:
+    --encoding ENC    入力の文字コード（auto, utf-8, shift_jis, euc-jp, iso-2022-jp, utf-16le, utf-16be。既定はutf-8）
+    --lossy           変換できないバイトをU+FFFDに置き換える
+    --mode MODE       数える対象（char, grapheme, word, line, morpheme, byte。既定はword）
+                      byteは文字コードを変換せずにバイトを数え、16進数で表示する
+    --trim            --mode lineで、行の先頭と末尾の空白を取り除く
+    --collapse-whitespace
+                      --mode lineで、連続する空白を1つの半角スペースに置き換える
+    --skip-blank      --mode lineで、空白だけの行を数えない
+    --ignore-case     --mode lineで、大文字と小文字を区別せずに数え、最初に出現した行で表示する
+    --base-form       --mode morphemeで、表層形の代わりに原形を数える
+    --with-pos        --mode morphemeで、東京/名詞のように品詞を付けて数える
+    --pos LIST        --mode morphemeで、指定した品詞だけを数える（noun, verb, adjectiveなど。カンマ区切り）
+    --dict DIR        --mode morphemeで、同梱の辞書の代わりにディレクトリー内のIPADICを使用する
+    --ngram N         連続するN個の文字、単語またはバイトを数える（--mode char, word, byteのみ）。
+                      --patternと指定した場合は、マッチした部分のN個の連続を数える
+    --pattern REGEX   単語の代わりに、正規表現にマッチした部分を数える
+    --group GROUP     --patternのうち、番号または名前で指定したキャプチャグループを数える
+    --casefold        大文字と小文字を区別せずに数える
+    --nfc             トークンをNFCに正規化する
+    --nfkc            トークンをNFKCに正規化する
+    --width           全角英数字を半角に、半角カタカナを全角に変換する
+    --kana KANA       かなを統一する（hiragana, katakana）
+    --stopwords LANGS 同梱のストップワードを数えない（en, ja。カンマ区切りで複数指定可）
+    --stopwords-file PATH
+                      ファイルに1行に1語ずつ記録されたストップワードを数えない
+    --stem            英単語を語幹に変換して数える
+    --stem-surface    英単語を語幹に変換して数え、語幹ごとに最も多く出現した語形で表示する
+    --jobs N          N個のスレッドで分担して数える（UTF-8のファイルのみ。既定は1）
+    --approx-top K    出現頻度が高いK個のトークンを、限られたメモリーで近似的に数える
+    --memory SIZE     --approx-topで使用するメモリーの量（64Mのように指定。既定は64M）
+    --distinct        異なるトークンの数だけを、HyperLogLogで推定する
+    --precision P     --distinctの精度（4から18。既定は14）
+    --stats           出現頻度の代わりに、エントロピーやYuleのKなどの統計量を表示する
+    --measure MEASURE compareで並べる基準（ll, chi2, log-ratio。既定はll）、
+                      またはcollocationsで並べる基準（pmi, t-score, ll。既定はll）
+    --top N           tfidfでファイルごとに、またはcollocationsで表示する数（既定は10）
+    --min-count N     collocationsで、N回以上出現した組だけを表示する（既定は2）
+    --context N       kwicで、前後それぞれN個のトークンを表示する（既定は5）
+    --context-chars N kwicで、前後それぞれN文字を表示する
+    --bm25            tfidfで、TF-IDFの代わりにBM25で重みを求める
+    --format FORMAT   結果を出力する形式（json, ndjson, csv, tsv, table）。指定しない場合は、
+                      Rustのデバッグ表記で出力する";
+
+/// `--memory`を指定しなかった場合に、近似的に数えるために使用するメモリーの量
+const DEFAULT_MEMORY: usize = 64 << 20;
+
+/// `64M`のように、`K`、`M`または`G`の単位を付けられるバイト数を解析する。
+fn parse_size(s: &str) -> Option<usize> {
+    let (digits, shift) = match s.as_bytes().last()?.to_ascii_uppercase() {
+        b'K' => (&s[..s.len() - 1], 10),
+        b'M' => (&s[..s.len() - 1], 20),
+        b'G' => (&s[..s.len() - 1], 30),
+        _ => (s, 0),
+    };
+    digits.parse::<usize>().ok()?.checked_mul(1 << shift)
+}
+
+/// コマンドライン引数
+struct Args {
+    command: Command,
+    /// `compare`の場合は対象と参照の2つのファイル、`tfidf`の場合はファイルまたはディレクトリー
+    filenames: Vec<String>,
+    /// `None`の場合は文字コードを自動判別する。
+    encoding: Option<Encoding>,
+    lossy: bool,
+    pattern: Option<Pattern>,
+    normalizer: Normalizer,
+    /// `None`の場合はストップワードを除外しない。
+    stopwords: Option<Stopwords>,
+    option: CountOption,
+    /// `--mode morpheme`で使用する辞書。`None`の場合は同梱の辞書を使用する。
+    dictionary: Option<Dictionary>,
+    stem: Stemming,
+    jobs: usize,
+    /// `Some(k)`の場合は、出現頻度が高い`k`個のトークンを近似的に数える。
+    approx_top: Option<usize>,
+    memory: usize,
+    /// `Some(precision)`の場合は、異なるトークンの数を推定する。
+    distinct: Option<u8>,
+    /// `true`の場合は、出現頻度の代わりに統計量を表示する。
+    stats: bool,
+    /// `Some(format)`の場合は、結果を`format`の形式で出力する。
+    format: Option<Format>,
+    /// `compare`で特徴語を並べる基準
+    measure: Measure,
+    /// `collocations`で組を並べる基準
+    association: Association,
+    /// `tfidf`でファイルごとに、または`collocations`で表示する数
+    top: usize,
+    /// `collocations`で表示する組の出現頻度の下限
+    min_count: usize,
+    /// `kwic`で探すトークン
+    keyword: String,
+    /// `kwic`で表示する文脈の長さ
+    context: Context,
+    /// `tfidf`で、TF-IDFの代わりにBM25を使用する。
+    bm25: bool,
+}
+
+/// サブコマンド
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Command {
+    /// 1つのファイルの出現頻度を数える。
+    Count,
+    /// 2つのファイルの出現頻度を比較する。
+    Compare,
+    /// 複数のファイルの出現頻度から、ファイルごとにTF-IDFを求める。
+    Tfidf,
+    /// 隣り合うトークンの組を数え、連語を求める。
+    Collocations,
+    /// トークンの出現箇所を、前後の文脈とともに表示する。
+    Kwic,
+    /// トークンごとの出現箇所の索引を出力する。
+    Index,
+}
+
+/// ステミングの方法
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Stemming {
+    /// ステミングしない。
+    None,
+    /// 語幹で表示する。
+    Stem,
+    /// 語幹ごとに最も多く出現した語形で表示する。
+    Surface,
+}
+
+impl Args {
+    fn parse(args: impl Iterator<Item = String>) -> Result<Args, String> {
+        let mut args = args.peekable();
+        let command = match args.peek().map(String::as_str) {
+            Some("compare") => {
+                args.next();
+                Command::Compare
+            }
+            Some("tfidf") => {
+                args.next();
+                Command::Tfidf
+            }
+            Some("collocations") => {
+                args.next();
+                Command::Collocations
+            }
+            Some("kwic") => {
+                args.next();
+                Command::Kwic
+            }
+            Some("index") => {
+                args.next();
+                Command::Index
+            }
+            _ => Command::Count,
+        };
+        let mut filenames = Vec::new();
+        let mut encoding = Some(Encoding::Utf8);
+        let mut lossy = false;
+        let mut pattern = None;
+        let mut group = None;
+        let mut normalizer = Normalizer::default();
+        let mut stopwords: Option<Stopwords> = None;
+        let mut stem = Stemming::None;
+        let mut jobs = None;
+        let mut option = None;
+        let mut morpheme_option = MorphemeOption::default();
+        let mut dictionary = None;
+        let mut ngram = None;
+        let mut line_option = LineOption::default();
+        let mut approx_top = None;
+        let mut memory = None;
+        let mut distinct = false;
+        let mut precision = None;
+        let mut stats = false;
+        let mut format = None;
+        let mut measure = None;
+        let mut top = None;
+        let mut bm25 = false;
+        let mut min_count = None;
+        let mut context = None;
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--encoding" => {
+                    let value = args.next().ok_or("--encoding requires a value")?;
+                    encoding = match value.as_str() {
+                        "auto" => None,
+                        _ => Some(value.parse().map_err(|e| format!("{}", e))?),
+                    };
+                }
+                "--lossy" => lossy = true,
+                "--mode" => {
+                    option = Some(match args.next().as_deref() {
+                        Some("char") => CountOption::Char,
+                        Some("grapheme") => CountOption::Grapheme,
+                        Some("word") => CountOption::Word,
+                        Some("line") => CountOption::Line,
+                        Some("morpheme") => CountOption::Morpheme(Default::default()),
+                        Some("byte") => CountOption::Byte,
+                        _ => {
+                            return Err(
+                                "--mode requires char, grapheme, word, line, morpheme or byte"
+                                    .to_string(),
+                            )
+                        }
+                    })
+                }
+                "--trim" => line_option.trim = true,
+                "--collapse-whitespace" => line_option.collapse_whitespace = true,
+                "--skip-blank" => line_option.skip_blank = true,
+                "--ignore-case" => line_option.ignore_case = true,
+                "--base-form" => morpheme_option.base_form = true,
+                "--with-pos" => morpheme_option.with_pos = true,
+                "--pos" => {
+                    let list = args.next().ok_or("--pos requires a value")?;
+                    morpheme_option.pos = list
+                        .split(',')
+                        .map(str::parse)
+                        .collect::<Result<PosSet, _>>()?;
+                }
+                "--dict" => dictionary = Some(args.next().ok_or("--dict requires a value")?),
+                "--ngram" => ngram = Some(args.next().ok_or("--ngram requires a value")?),
+                "--pattern" => pattern = Some(args.next().ok_or("--pattern requires a value")?),
+                "--group" => group = Some(args.next().ok_or("--group requires a value")?),
+                "--casefold" => normalizer.case_fold = true,
+                "--nfc" => normalizer.form = Some(UnicodeForm::Nfc),
+                "--nfkc" => normalizer.form = Some(UnicodeForm::Nfkc),
+                "--width" => normalizer.width = true,
+                "--kana" => {
+                    normalizer.kana = match args.next().as_deref() {
+                        Some("hiragana") => Some(Kana::Hiragana),
+                        Some("katakana") => Some(Kana::Katakana),
+                        _ => return Err("--kana requires hiragana or katakana".to_string()),
+                    }
+                }
+                "--stopwords" => {
+                    let value = args.next().ok_or("--stopwords requires a value")?;
+                    for code in value.split(',') {
+                        let list = Stopwords::for_language(code)
+                            .ok_or_else(|| format!("unknown stopword language: {}", code))?;
+                        stopwords.get_or_insert_with(Stopwords::new).extend(list);
+                    }
+                }
+                "--stopwords-file" => {
+                    let path = args.next().ok_or("--stopwords-file requires a value")?;
+                    let list =
+                        Stopwords::from_file(&path).map_err(|e| format!("{}: {}", path, e))?;
+                    stopwords.get_or_insert_with(Stopwords::new).extend(list);
+                }
+                "--stem" => stem = Stemming::Stem,
+                "--stem-surface" => stem = Stemming::Surface,
+                "--approx-top" => {
+                    approx_top = Some(args.next().ok_or("--approx-top requires a value")?)
+                }
+                "--memory" => memory = Some(args.next().ok_or("--memory requires a value")?),
+                "--distinct" => distinct = true,
+                "--precision" => {
+                    precision = Some(args.next().ok_or("--precision requires a value")?)
+                }
+                "--stats" => stats = true,
+                "--format" => {
+                    let value = args.next().ok_or("--format requires a value")?;
+                    format = Some(value.parse().map_err(|e| format!("{}", e))?);
+                }
+                "--measure" => measure = Some(args.next().ok_or("--measure requires a value")?),
+                "--jobs" => jobs = Some(args.next().ok_or("--jobs requires a value")?),
+                "--top" => top = Some(args.next().ok_or("--top requires a value")?),
+                "--bm25" => bm25 = true,
+                "--context" => {
+                    let n = args.next().ok_or("--context requires a value")?;
+                    let n = n.parse().map_err(|_| "--context requires a number")?;
+                    context = Some(Context::Tokens(n));
+                }
+                "--context-chars" => {
+                    let n = args.next().ok_or("--context-chars requires a value")?;
+                    let n = n.parse().map_err(|_| "--context-chars requires a number")?;
+                    context = Some(Context::Chars(n));
+                }
+                "--min-count" => {
+                    min_count = Some(args.next().ok_or("--min-count requires a value")?)
+                }
+                _ if arg.starts_with("--") => return Err(format!("unknown option: {}", arg)),
+                _ => filenames.push(arg),
+            }
+        }
+
+        let pattern = match (pattern, group) {
+            (Some(pattern), group) => {
+                let pattern = Pattern::new(&pattern).map_err(|e| e.to_string())?;
+                let pattern = match group {
+                    Some(group) => match group.parse() {
+                        Ok(index) => pattern.group(index),
+                        Err(_) => pattern.named_group(&group),
+                    }
+                    .map_err(|e| e.to_string())?,
+                    None => pattern,
+                };
+                Some(pattern)
+            }
+            (None, Some(_)) => return Err("--group requires --pattern".to_string()),
+            (None, None) => None,
+        };
+
+        let memory = match (memory, &approx_top) {
+            (Some(memory), Some(_)) => {
+                parse_size(&memory).ok_or_else(|| format!("invalid memory size: {}", memory))?
+            }
+            (Some(_), None) => return Err("--memory requires --approx-top".to_string()),
+            (None, _) => DEFAULT_MEMORY,
+        };
+        let approx_top = match approx_top {
+            Some(k) => Some(
+                k.parse()
+                    .map_err(|_| "--approx-top requires a number".to_string())?,
+            ),
+            None => None,
+        };
+        if pattern.is_some() && option.is_some() {
+            return Err("--pattern cannot be combined with --mode".to_string());
+        }
+        let mut option = option.unwrap_or_default();
+        if morpheme_option != MorphemeOption::default() || dictionary.is_some() {
+            if option != CountOption::Morpheme(MorphemeOption::default()) {
+                return Err(
+                    "--base-form, --with-pos, --pos and --dict require --mode morpheme".to_string(),
+                );
+            }
+            option = CountOption::Morpheme(morpheme_option);
+        }
+        let dictionary = match dictionary {
+            Some(dir) => {
+                Some(Dictionary::from_ipadic(&dir).map_err(|e| format!("{}: {}", dir, e))?)
+            }
+            None => None,
+        };
+        if line_option != LineOption::default() {
+            if option != CountOption::Line {
+                return Err(
+                    "--trim, --collapse-whitespace, --skip-blank and --ignore-case require --mode line"
+                        .to_string(),
+                );
+            }
+            option = CountOption::LineWith(line_option);
+        }
+        if let Some(n) = ngram {
+            let n = n
+                .parse()
+                .map_err(|_| "--ngram requires a number".to_string())?;
+            option = match option {
+                CountOption::Char => CountOption::CharNgram {
+                    n,
+                    cross_lines: false,
+                },
+                CountOption::Word => CountOption::WordNgram {
+                    n,
+                    cross_lines: false,
+                },
+                CountOption::Byte => CountOption::ByteNgram { n },
+                _ => return Err("--ngram requires --mode char, word or byte".to_string()),
+            };
+        }
+        let is_byte = matches!(option, CountOption::Byte | CountOption::ByteNgram { .. });
+        if is_byte && (approx_top.is_some() || distinct) {
+            return Err(
+                "--mode byte cannot be combined with --approx-top or --distinct".to_string(),
+            );
+        }
+
+        if stats && (approx_top.is_some() || distinct) {
+            return Err("--stats cannot be combined with --approx-top or --distinct".to_string());
+        }
+
+        let distinct = match (distinct, precision) {
+            (true, Some(precision)) => match precision.parse() {
+                Ok(p) if (MIN_PRECISION..=MAX_PRECISION).contains(&p) => Some(p),
+                _ => {
+                    return Err(format!(
+                        "--precision requires a number from {} to {}",
+                        MIN_PRECISION, MAX_PRECISION
+                    ))
+                }
+            },
+            (true, None) => Some(DEFAULT_PRECISION),
+            (false, Some(_)) => return Err("--precision requires --distinct".to_string()),
+            (false, None) => None,
+        };
+
+        match command {
+            Command::Count | Command::Collocations if filenames.len() != 1 => {
+                return Err("1 argument FILENAME required".to_string())
+            }
+            Command::Compare if filenames.len() != 2 => {
+                return Err("2 arguments TARGET and REFERENCE required".to_string())
+            }
+            Command::Tfidf | Command::Index if filenames.is_empty() => {
+                return Err("at least 1 argument PATH required".to_string())
+            }
+            Command::Kwic if filenames.len() < 2 => {
+                return Err("arguments KEYWORD and at least 1 PATH required".to_string())
+            }
+            Command::Compare
+            | Command::Tfidf
+            | Command::Collocations
+            | Command::Kwic
+            | Command::Index
+                if stats || approx_top.is_some() || distinct.is_some() =>
+            {
+                return Err(
+                    "subcommands cannot be combined with --stats, --approx-top or --distinct"
+                        .to_string(),
+                )
+            }
+            Command::Collocations | Command::Kwic | Command::Index if is_byte => {
+                return Err(
+                    "collocations, kwic and index cannot be combined with --mode byte".to_string(),
+                )
+            }
+            _ => {}
+        }
+        let (measure, association) = match (command, measure.as_deref()) {
+            (_, None) => (Measure::default(), Association::default()),
+            (Command::Compare, Some(measure)) => {
+                let measure = match measure {
+                    "ll" => Measure::LogLikelihood,
+                    "chi2" => Measure::ChiSquare,
+                    "log-ratio" => Measure::LogRatio,
+                    _ => return Err("--measure requires ll, chi2 or log-ratio".to_string()),
+                };
+                (measure, Association::default())
+            }
+            (Command::Collocations, Some(association)) => {
+                let association = match association {
+                    "pmi" => Association::Pmi,
+                    "t-score" => Association::TScore,
+                    "ll" => Association::LogLikelihood,
+                    _ => return Err("--measure requires pmi, t-score or ll".to_string()),
+                };
+                (Measure::default(), association)
+            }
+            _ => return Err("--measure requires compare or collocations".to_string()),
+        };
+        if top.is_some() && !matches!(command, Command::Tfidf | Command::Collocations) {
+            return Err("--top requires tfidf or collocations".to_string());
+        }
+        if bm25 && command != Command::Tfidf {
+            return Err("--bm25 requires tfidf".to_string());
+        }
+        if min_count.is_some() && command != Command::Collocations {
+            return Err("--min-count requires collocations".to_string());
+        }
+        if context.is_some() && command != Command::Kwic {
+            return Err("--context and --context-chars require kwic".to_string());
+        }
+        let keyword = match command {
+            Command::Kwic => filenames.remove(0),
+            _ => String::new(),
+        };
+        let min_count = match min_count {
+            Some(min_count) => min_count
+                .parse()
+                .map_err(|_| "--min-count requires a number".to_string())?,
+            None => 2,
+        };
+        let top = match top {
+            Some(top) => top
+                .parse()
+                .map_err(|_| "--top requires a number".to_string())?,
+            None => 10,
+        };
+
+        let jobs = match jobs {
+            Some(jobs) => match jobs.parse() {
+                Ok(jobs) if jobs > 0 => jobs,
+                _ => return Err("--jobs requires a positive number".to_string()),
+            },
+            None => 1,
+        };
+
+        Ok(Args {
+            command,
+            filenames,
+            encoding,
+            lossy,
+            pattern,
+            normalizer,
+            stopwords,
+            option,
+            dictionary,
+            stem,
+            jobs,
+            approx_top,
+            memory,
+            distinct,
+            stats,
+            measure,
+            association,
+            top,
+            min_count,
+            bm25,
+            keyword,
+            context: context.unwrap_or(Context::Tokens(5)),
+            format,
+        })
+    }
+
+    /// 正規化やストップワードの除外、ステミングをする前のトークナイザーを作成する。
+    fn base_tokenizer(&self) -> Box<dyn Tokenizer + '_> {
+        match (&self.pattern, self.option) {
+            // パターンにマッチした部分を、単語と同様に半角スペースで連結する。
+            (Some(pattern), CountOption::WordNgram { n, cross_lines }) => {
+                Box::new(NgramTokenizer::new(pattern.clone(), n, " ", cross_lines))
+            }
+            (Some(pattern), _) => Box::new(pattern.clone()),
+            (None, CountOption::Morpheme(option)) => {
+                let dictionary = self.dictionary.as_ref();
+                let dictionary = dictionary.unwrap_or_else(|| Dictionary::bundled());
+                Box::new(MorphemeTokenizer::new(dictionary, option))
+            }
+            (None, option) => option.tokenizer(),
+        }
+    }
+
+    /// `kwic`でトークンを比較するために、指定されたオプションに従って正規化とステミングをする。
+    fn match_key(&self, token: &str) -> String {
+        let token = self.normalizer.normalize(token);
+        match self.stem {
+            Stemming::None => token,
+            Stemming::Stem | Stemming::Surface => stem(&token),
+        }
+    }
+
+    /// `index`で記録するキーを、指定されたオプションに従って求める。ストップワードの場合は`None`を返す。
+    fn index_key(&self, token: &str) -> Option<String> {
+        let token = self.normalizer.normalize(token);
+        if token.is_empty() || self.stopwords.as_ref().is_some_and(|s| s.contains(&token)) {
+            return None;
+        }
+        match self.stem {
+            Stemming::None => Some(token),
+            Stemming::Stem | Stemming::Surface => Some(stem(&token)),
+        }
+    }
+
+    /// 指定されたオプションに従って、トークナイザーを作成する。
+    fn tokenizer(&self) -> Box<dyn Tokenizer + '_> {
+        let mut tokenizer = self.base_tokenizer();
+        if !self.normalizer.is_identity() {
+            tokenizer = Box::new(NormalizedTokenizer::new(tokenizer, self.normalizer));
+        }
+        // 正規化した後のトークンからストップワードを取り除く。
+        if let Some(stopwords) = &self.stopwords {
+            tokenizer = Box::new(StopwordTokenizer::new(tokenizer, stopwords.clone()));
+        }
+        if self.stem == Stemming::Stem {
+            tokenizer = Box::new(StemTokenizer::new(tokenizer));
+        }
+        tokenizer
+    }
+}
 
 fn main() {
     // 1. コマンドラインで指定された引数を読み込む。
-    let filename = env::args().nth(1).expect("1 argument FILENAME required");
+    let args = Args::parse(env::args().skip(1)).unwrap_or_else(|e| {
+        eprintln!("{}\n\n{}", e, USAGE);
+        process::exit(2);
+    });
+    if args.command == Command::Compare {
+        // 2. 対象と参照のファイルをそれぞれ数え、対象に特徴的なトークンを求める。
+        let target = Frequencies::from(count_frequencies(&args, &args.filenames[0]));
+        let reference = Frequencies::from(count_frequencies(&args, &args.filenames[1]));
+        let keywords = compare(&target, &reference, args.measure);
+        if let Some(format) = args.format {
+            let mut table = Table::new([
+                "key",
+                "target",
+                "reference",
+                "log_likelihood",
+                "chi_square",
+                "log_ratio",
+            ]);
+            for k in &keywords {
+                table.push(vec![
+                    k.key.into(),
+                    k.target.into(),
+                    k.reference.into(),
+                    k.log_likelihood.into(),
+                    k.chi_square.into(),
+                    k.log_ratio.into(),
+                ]);
+            }
+            print_table(&table, format);
+            return;
+        }
+        let keywords: Vec<_> = keywords
+            .iter()
+            .map(|k| {
+                (
+                    k.key,
+                    k.target,
+                    k.reference,
+                    k.log_likelihood,
+                    k.chi_square,
+                    k.log_ratio,
+                )
+            })
+            .collect();
+        println!("{:?}", keywords);
+        return;
+    }
+    if args.command == Command::Tfidf {
+        // 2. ファイルごとに数え、各トークンが出現したファイルの数からTF-IDFを求める。
+        let mut corpus = Corpus::new();
+        let mut failed = false;
+        for path in &args.filenames {
+            for filename in list_files(path) {
+                let result = try_count_frequencies(&args, &filename);
+                if let Some(freqs) = skip_on_error(&filename, result, &mut failed) {
+                    corpus.add(filename, Frequencies::from(freqs));
+                }
+            }
+        }
+        let mut table = Table::new(["document", "term", "count", "weight"]);
+        for (i, document) in corpus.documents().iter().enumerate() {
+            let mut weights = if args.bm25 {
+                corpus.bm25(i, Bm25::default())
+            } else {
+                corpus.tf_idf(i)
+            };
+            weights.truncate(args.top);
+            if args.format.is_some() {
+                for w in &weights {
+                    table.push(vec![
+                        document.name().into(),
+                        w.term.into(),
+                        w.count.into(),
+                        w.weight.into(),
+                    ]);
+                }
+                continue;
+            }
+            let weights: Vec<_> = weights.iter().map(|w| (w.term, w.weight)).collect();
+            println!("{}: {:?}", document.name(), weights);
+        }
+        if let Some(format) = args.format {
+            print_table(&table, format);
+        }
+        exit_if_failed(failed);
+        return;
+    }
+
+    if args.command == Command::Kwic {
+        // 2. 正規化やステミングをした結果で比較して、出現箇所を文脈とともに取り出す。
+        let keyword = args.match_key(&args.keyword);
+        let mut rows = Vec::new();
+        let mut failed = false;
+        for path in &args.filenames {
+            for filename in list_files(path) {
+                let result = count_decoded(&args, &filename, |decoder| {
+                    let is_keyword = |token: &str| args.match_key(token) == keyword;
+                    try_find_by(decoder, args.base_tokenizer(), is_keyword, args.context)
+                });
+                let occurrences = skip_on_error(&filename, result, &mut failed);
+                for occurrence in occurrences.into_iter().flatten() {
+                    rows.push((filename.clone(), occurrence));
+                }
+            }
+        }
+        if let Some(format) = args.format {
+            let mut table = Table::new(["file", "line", "offset", "left", "keyword", "right"]);
+            for (filename, o) in rows {
+                table.push(vec![
+                    filename.into(),
+                    o.line.into(),
+                    o.offset.into(),
+                    o.left.into(),
+                    o.keyword.into(),
+                    o.right.into(),
+                ]);
+            }
+            print_table(&table, format);
+            exit_if_failed(failed);
+            return;
+        }
+        // キーワードの位置を揃えて表示する。
+        let rows: Vec<_> = rows
+            .into_iter()
+            .map(|(filename, o)| (format!("{}:{}:", filename, o.line), o))
+            .collect();
+        let label_width = rows.iter().map(|(label, _)| display_width(label)).max();
+        let left_width = rows.iter().map(|(_, o)| display_width(&o.left)).max();
+        for (label, o) in &rows {
+            println!(
+                "{}{} {}{}{}{}",
+                label,
+                " ".repeat(label_width.unwrap_or(0) - display_width(label)),
+                " ".repeat(left_width.unwrap_or(0) - display_width(&o.left)),
+                o.left,
+                o.keyword,
+                o.right
+            );
+        }
+        exit_if_failed(failed);
+        return;
+    }
+
+    if args.command == Command::Index {
+        // 2. ファイルごとに、正規化する前のトークンの位置を記録する。
+        let mut index = InvertedIndex::new();
+        let mut failed = false;
+        for path in &args.filenames {
+            for filename in list_files(path) {
+                let result = count_decoded(&args, &filename, |decoder| {
+                    let key = |token: &str| args.index_key(token);
+                    index.add_by(filename.as_str(), decoder, args.base_tokenizer(), key)
+                });
+                skip_on_error(&filename, result, &mut failed);
+            }
+        }
+        if let Some(format) = args.format {
+            // 位置ごとに1行とし、トークンの辞書順に並べる。
+            let mut keys: Vec<_> = index.keys().collect();
+            keys.sort_unstable();
+            let mut table = Table::new(["key", "file", "line", "offset", "column"]);
+            for key in keys {
+                for p in index.positions(key) {
+                    table.push(vec![
+                        key.into(),
+                        index.files()[p.file].as_str().into(),
+                        p.line.into(),
+                        p.offset.into(),
+                        p.column.into(),
+                    ]);
+                }
+            }
+            print_table(&table, format);
+            exit_if_failed(failed);
+            return;
+        }
+        if let Err(e) = index.write_to(io::stdout().lock()) {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+        exit_if_failed(failed);
+        return;
+    }
+
+    let filename = &args.filenames[0];
+    if args.command == Command::Collocations {
+        // 2. 同じ行で隣り合うトークンの組を数え、結びつきの強さを求める。
+        let result = count_decoded(&args, filename, |decoder| {
+            try_count_collocations(decoder, args.tokenizer())
+        });
+        let collocations = exit_on_error(filename, result);
+        let mut ranked = collocations.rank(args.association, args.min_count);
+        ranked.truncate(args.top);
+        if let Some(format) = args.format {
+            let mut table = Table::new([
+                "first",
+                "second",
+                "count",
+                "pmi",
+                "t_score",
+                "log_likelihood",
+            ]);
+            for c in &ranked {
+                table.push(vec![
+                    c.first.into(),
+                    c.second.into(),
+                    c.count.into(),
+                    c.pmi.into(),
+                    c.t_score.into(),
+                    c.log_likelihood.into(),
+                ]);
+            }
+            print_table(&table, format);
+            return;
+        }
+        let ranked: Vec<_> = ranked
+            .iter()
+            .map(|c| {
+                (
+                    c.first,
+                    c.second,
+                    c.count,
+                    c.pmi,
+                    c.t_score,
+                    c.log_likelihood,
+                )
+            })
+            .collect();
+        println!("{:?}", ranked);
+        return;
+    }
+    if let Some(precision) = args.distinct {
+        // 2. 異なるトークンの数だけを、HyperLogLogで推定する。
+        let result = count_decoded(&args, filename, |decoder| {
+            try_count_distinct(decoder, args.tokenizer(), precision)
+        });
+        let hll = exit_on_error(filename, result);
+        if let Some(format) = args.format {
+            let mut table = Table::new(["estimate", "standard_error"]);
+            table.push(vec![hll.estimate().into(), hll.standard_error().into()]);
+            print_table(&table, format);
+            return;
+        }
+        println!(
+            "{:.0} (±{:.2}%)",
+            hll.estimate(),
+            hll.standard_error() * 100.0
+        );
+        return;
+    }
+    if let Some(k) = args.approx_top {
+        // 2. 出現頻度が高いトークンだけを、指定された量のメモリーで近似的に数える。
+        let result = count_decoded(&args, filename, |decoder| {
+            try_count_top(decoder, args.tokenizer(), k, args.memory)
+        });
+        let top = exit_on_error(filename, result);
+        if let Some(format) = args.format {
+            let mut table = Table::new(["key", "count", "error"]);
+            for h in top.top() {
+                table.push(vec![h.key.into(), h.count.into(), h.error.into()]);
+            }
+            print_table(&table, format);
+            return;
+        }
+        let hitters: Vec<_> = top
+            .top()
+            .iter()
+            .map(|h| (h.key, h.count, h.error))
+            .collect();
+        println!("{:?}", hitters);
+        return;
+    }
+
+    let freqs = count_frequencies(&args, filename);
+    match (args.stats, args.format) {
+        (true, Some(format)) => {
+            let stats = Statistics::new(&Frequencies::from(freqs));
+            let mut table = Table::new(["name", "value"]);
+            let rows: [(&str, Value); 9] = [
+                ("tokens", stats.tokens.into()),
+                ("types", stats.types.into()),
+                ("entropy", stats.entropy.into()),
+                ("type_token_ratio", stats.type_token_ratio.into()),
+                ("hapax_legomena", stats.hapax_legomena.into()),
+                ("dis_legomena", stats.dis_legomena.into()),
+                ("yules_k", stats.yules_k.into()),
+                ("simpsons_d", stats.simpsons_d.into()),
+                ("zipf_exponent", stats.zipf_exponent.into()),
+            ];
+            for (name, value) in rows {
+                table.push(vec![name.into(), value]);
+            }
+            print_table(&table, format);
+        }
+        (true, None) => println!("{}", Statistics::new(&Frequencies::from(freqs))),
+        // 出現頻度が高い順、同じ場合は辞書順に並べる。
+        (false, Some(format)) => print_table(&Table::from(&Frequencies::from(freqs)), format),
+        (false, None) => println!("{:?}", freqs),
+    }
+}
+
+/// `table`を`format`の形式で標準出力に書き出す。エラーが発生した場合は、エラーを表示して終了する。
+fn print_table(table: &Table, format: Format) {
+    if let Err(e) = table.write(format, io::stdout().lock()) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
+
+/// `path`がディレクトリーの場合はその中のファイルを名前順に、それ以外は`path`だけを返す。
+///
+/// サブディレクトリーの中のファイルは含まない。
+fn list_files(path: &str) -> Vec<String> {
+    if !Path::new(path).is_dir() {
+        return vec![path.to_string()];
+    }
+    let entries = fs::read_dir(path).unwrap_or_else(|e| {
+        eprintln!("{}: {}", path, e);
+        process::exit(1);
+    });
+    let mut files: Vec<String> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file())
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect();
+    files.sort();
+    files
+}
+
+/// `filename`のトークンの出現頻度を数える。エラーが発生した場合は、エラーを表示して終了する。
+fn count_frequencies(args: &Args, filename: &str) -> HashMap<String, usize> {
+    exit_on_error(filename, try_count_frequencies(args, filename))
+}
+
+/// `filename`のトークンの出現頻度を数える。
+fn try_count_frequencies(
+    args: &Args,
+    filename: &str,
+) -> Result<HashMap<String, usize>, CountError> {
+    if let CountOption::Byte | CountOption::ByteNgram { .. } = args.option {
+        // 文字コードを変換せずに、ファイルのバイトを数える。
+        return try_count_file(filename, args.option, 1);
+    }
+    let jobs = effective_jobs(args.option, args.jobs);
+    let parallel = jobs > 1
+        && args.encoding == Some(Encoding::Utf8)
+        && !args.lossy
+        && args.stem != Stemming::Surface;
+    if parallel {
+        // ファイルを行の区切りで分割し、複数のスレッドで分担して数える。
+        try_count_file_with(filename, jobs, || args.tokenizer())
+    } else {
+        count_decoded(args, filename, |decoder| {
+            let tokenizer = args.tokenizer();
+            match args.stem {
+                // 語幹ごとに、最も多く出現した語形を表示に使用する。
+                Stemming::Surface => try_count_stems(decoder, tokenizer).map(|stems| {
+                    stems
+                        .values()
+                        .map(|count| (count.most_frequent_surface().to_string(), count.count()))
+                        .collect()
+                }),
+                _ => try_count_with(decoder, tokenizer),
+            }
+        })
+    }
+}
+
+/// `filename`を開いて指定された文字コードで変換し、`f`で先頭から1行ずつ読み込んで数える。
+fn count_decoded<T>(
+    args: &Args,
+    filename: &str,
+    f: impl FnOnce(&mut Decoder<BufReader<File>>) -> Result<T, CountError>,
+) -> Result<T, CountError> {
     // 2. コマンドラインで指定されたファイルを開く。
-    let file = File::open(filename).unwrap();
-    let reader = BufReader::new(&file);
+    let open_error = |source| CountError::Io {
+        line: 1,
+        offset: 0,
+        source,
+    };
+    let reader = BufReader::new(File::open(filename).map_err(open_error)?);
+    let decoder = match args.encoding {
+        Some(encoding) => Decoder::new(reader, encoding),
+        None => Decoder::detect(reader).map_err(open_error)?,
+    };
+    let mut decoder = decoder.lossy(args.lossy);
 
     // 3. ファイルから1行ずつ読み込む。
-    let freqs = count(reader, Default::default());
-    println!("{:?}", freqs);
+    let result = f(&mut decoder);
+    if decoder.replacements() > 0 {
+        eprintln!(
+            "{}: {}個のバイト列をU+FFFDに置き換えました",
+            filename,
+            decoder.replacements()
+        );
+    }
+    result
+}
+
+/// エラーが発生した場合は、エラーを表示して`None`を返し、`failed`を`true`にする。
+///
+/// 複数のファイルを扱うサブコマンドで、エラーが発生したファイルを飛ばして続けるために使用する。
+fn skip_on_error<T>(filename: &str, result: Result<T, CountError>, failed: &mut bool) -> Option<T> {
+    match result {
+        Ok(value) => Some(value),
+        Err(e) => {
+            eprintln!("{}: {}", filename, e);
+            *failed = true;
+            None
+        }
+    }
+}
+
+/// 飛ばしたファイルがあった場合は、結果を表示した後に異常終了する。
+fn exit_if_failed(failed: bool) {
+    if failed {
+        process::exit(1);
+    }
+}
+
+/// エラーが発生した場合は、エラーを表示して終了する。
+fn exit_on_error<T>(filename: &str, result: Result<T, CountError>) -> T {
+    result.unwrap_or_else(|e| {
+        eprintln!("{}: {}", filename, e);
+        process::exit(1);
+    })
 }
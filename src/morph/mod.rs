@@ -0,0 +1,787 @@
+//! 辞書とラティス（Viterbiアルゴリズム）を使用して、日本語の文を形態素に分割する機能を提供する。
+//!
+//! 辞書の形式はMeCabのIPADICと同じで、語彙（`*.csv`）、連接コスト表（`matrix.def`）及び
+//! 未知語の定義（`unk.def`）から構成される。クレートには、基本的な語彙だけを収録した小さな辞書が
+//! 同梱されており、[`Dictionary::bundled`](struct.Dictionary.html#method.bundled)で取得できる。
+//! 実用的な精度が必要な場合は、[`Dictionary::from_ipadic`](struct.Dictionary.html#method.from_ipadic)
+//! でIPADICを読み込むこと。
+//!
+//! ```
+//! use kuroyasu_bicycle_book_wordcount::morph::Dictionary;
+//!
+//! let dict = Dictionary::bundled();
+//! let surfaces: Vec<_> = dict
+//!     .tokenize("東京都に住んでいる")
+//!     .iter()
+//!     .map(|m| m.surface())
+//!     .collect();
+//! assert_eq!(surfaces, ["東京", "都", "に", "住ん", "で", "いる"]);
+//! ```
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use crate::encoding::Decoder;
+
+/// 同梱する辞書の語彙
+const BUNDLED_LEXICON: &str = include_str!("dict/bundled.csv");
+/// 同梱する辞書の連接コスト表
+const BUNDLED_MATRIX: &str = include_str!("dict/matrix.def");
+/// 同梱する辞書の未知語の定義
+const BUNDLED_UNKNOWN: &str = include_str!("dict/unk.def");
+
+/// 文頭及び文末を表す文脈ID
+const BOS_EOS_ID: u16 = 0;
+
+/// 辞書の1語
+#[derive(Debug, Clone)]
+struct Entry {
+    left_id: u16,
+    right_id: u16,
+    cost: i32,
+    /// 品詞、品詞細分類1〜3、活用型、活用形、原形、読み及び発音をカンマで区切ったもの
+    features: Box<str>,
+}
+
+/// 形態素解析に使用する辞書
+#[derive(Debug, Clone)]
+pub struct Dictionary {
+    entries: HashMap<Box<str>, Vec<Entry>>,
+    /// 語彙の中で最も長い表層形の文字数
+    max_chars: usize,
+    unknown: HashMap<CharClass, Vec<Entry>>,
+    left_size: usize,
+    right_size: usize,
+    /// `前の語の右文脈ID * right_size + 次の語の左文脈ID`の位置に連接コストを記録する。
+    matrix: Vec<i32>,
+}
+
+impl Dictionary {
+    /// クレートに同梱されている辞書を返す。
+    ///
+    /// 同梱の辞書は、よく使われる語と機能語だけを収録した小さなものである。
+    pub fn bundled() -> &'static Dictionary {
+        static BUNDLED: OnceLock<Dictionary> = OnceLock::new();
+        BUNDLED.get_or_init(|| {
+            Dictionary::from_readers(
+                BUNDLED_LEXICON.as_bytes(),
+                BUNDLED_MATRIX.as_bytes(),
+                BUNDLED_UNKNOWN.as_bytes(),
+            )
+            .expect("bundled dictionary is valid")
+        })
+    }
+
+    /// IPADICのディレクトリから辞書を読み込む。
+    ///
+    /// ディレクトリ内の全ての`*.csv`、`matrix.def`及び`unk.def`を読み込む。
+    /// ファイルの文字コードは自動判別するため、EUC-JPで配布されているIPADICをそのまま読み込める。
+    ///
+    /// # Errors
+    ///
+    /// ファイルの読み込みに失敗した場合、またはファイルの形式が正しくない場合は、エラーを返す。
+    pub fn from_ipadic(dir: impl AsRef<Path>) -> io::Result<Dictionary> {
+        let dir = dir.as_ref();
+        let open = |path: &Path| -> io::Result<Decoder<BufReader<File>>> {
+            Decoder::detect(BufReader::new(File::open(path)?))
+        };
+
+        let mut lexicons = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "csv") {
+                lexicons.push(path);
+            }
+        }
+        // 読み込む順序によって、同じ表層形の語の順序が変わらないようにする。
+        lexicons.sort();
+
+        let mut dict =
+            Dictionary::empty(open(&dir.join("matrix.def"))?, open(&dir.join("unk.def"))?)?;
+        for path in lexicons {
+            dict.add_lexicon(open(&path)?)?;
+        }
+        Ok(dict)
+    }
+
+    /// 語彙、連接コスト表及び未知語の定義を、それぞれのリーダーから読み込む。
+    ///
+    /// # Errors
+    ///
+    /// 読み込みに失敗した場合、または形式が正しくない場合は、エラーを返す。
+    pub fn from_readers(
+        lexicon: impl BufRead,
+        matrix: impl BufRead,
+        unknown: impl BufRead,
+    ) -> io::Result<Dictionary> {
+        let mut dict = Dictionary::empty(matrix, unknown)?;
+        dict.add_lexicon(lexicon)?;
+        Ok(dict)
+    }
+
+    /// 語彙が空の辞書を作成する。
+    fn empty(matrix: impl BufRead, unknown: impl BufRead) -> io::Result<Dictionary> {
+        let mut lines = matrix.lines().enumerate();
+        let (left_size, right_size) = match lines.next() {
+            Some((_, header)) => {
+                let header = header?;
+                let mut sizes = header.split_whitespace().map(str::parse::<usize>);
+                match (sizes.next(), sizes.next()) {
+                    (Some(Ok(left)), Some(Ok(right))) => (left, right),
+                    _ => return Err(invalid_data("matrix.def", 1, &header)),
+                }
+            }
+            None => return Err(invalid_data("matrix.def", 1, "")),
+        };
+        let mut costs = vec![0; left_size * right_size];
+        for (i, line) in lines {
+            let line = line?;
+            let mut fields = line.split_whitespace();
+            let (Some(right), Some(left), Some(cost)) =
+                (fields.next(), fields.next(), fields.next())
+            else {
+                continue;
+            };
+            match (
+                right.parse::<usize>(),
+                left.parse::<usize>(),
+                cost.parse::<i32>(),
+            ) {
+                (Ok(right), Ok(left), Ok(cost)) if right < left_size && left < right_size => {
+                    costs[right * right_size + left] = cost;
+                }
+                _ => return Err(invalid_data("matrix.def", i + 1, &line)),
+            }
+        }
+
+        let mut dict = Dictionary {
+            entries: HashMap::new(),
+            max_chars: 0,
+            unknown: HashMap::new(),
+            left_size,
+            right_size,
+            matrix: costs,
+        };
+        for (i, line) in unknown.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let (name, entry) = dict
+                .parse_entry(&line)
+                .ok_or_else(|| invalid_data("unk.def", i + 1, &line))?;
+            let class =
+                CharClass::from_name(name).ok_or_else(|| invalid_data("unk.def", i + 1, &line))?;
+            dict.unknown.entry(class).or_default().push(entry);
+        }
+        if !dict.unknown.contains_key(&CharClass::Default) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "unk.def: DEFAULT is not defined",
+            ));
+        }
+        Ok(dict)
+    }
+
+    /// 語彙を辞書に追加する。
+    fn add_lexicon(&mut self, lexicon: impl BufRead) -> io::Result<()> {
+        for (i, line) in lexicon.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let (surface, entry) = self
+                .parse_entry(&line)
+                .ok_or_else(|| invalid_data("lexicon", i + 1, &line))?;
+            self.max_chars = self.max_chars.max(surface.chars().count());
+            self.entries.entry(surface.into()).or_default().push(entry);
+        }
+        Ok(())
+    }
+
+    /// `表層形,左文脈ID,右文脈ID,コスト,素性...`の形式の行を解析する。
+    ///
+    /// カンマを含む表層形は、`","`のように二重引用符で囲む。
+    fn parse_entry<'a>(&self, line: &'a str) -> Option<(&'a str, Entry)> {
+        let (surface, rest) = match line.strip_prefix('"') {
+            Some(quoted) => {
+                let end = quoted.find("\",")?;
+                (&quoted[..end], &quoted[end + 2..])
+            }
+            None => line.split_once(',')?,
+        };
+        let mut fields = rest.splitn(4, ',');
+        let left_id = fields.next()?.parse::<u16>().ok()?;
+        let right_id = fields.next()?.parse::<u16>().ok()?;
+        let cost = fields.next()?.parse().ok()?;
+        let features = fields.next()?;
+        if surface.is_empty()
+            || usize::from(left_id) >= self.right_size
+            || usize::from(right_id) >= self.left_size
+        {
+            return None;
+        }
+        Some((
+            surface,
+            Entry {
+                left_id,
+                right_id,
+                cost,
+                features: features.into(),
+            },
+        ))
+    }
+
+    /// 前の語の右文脈IDと次の語の左文脈IDの連接コストを返す。
+    fn connection_cost(&self, right_id: u16, left_id: u16) -> i32 {
+        self.matrix[usize::from(right_id) * self.right_size + usize::from(left_id)]
+    }
+
+    /// `text`を形態素に分割する。
+    ///
+    /// 空白文字は形態素の区切りとして扱い、結果には含めない。
+    pub fn tokenize<'a>(&'a self, text: &'a str) -> Vec<Morpheme<'a>> {
+        let mut morphemes = Vec::new();
+        for segment in text.split(char::is_whitespace).filter(|s| !s.is_empty()) {
+            self.tokenize_segment(segment, &mut morphemes);
+        }
+        morphemes
+    }
+
+    /// 空白を含まない`text`を、ラティスを構築して最小コストの形態素の並びに分割する。
+    fn tokenize_segment<'a>(&'a self, text: &'a str, morphemes: &mut Vec<Morpheme<'a>>) {
+        // 文字の境界のバイト位置
+        let bounds: Vec<usize> = text
+            .char_indices()
+            .map(|(i, _)| i)
+            .chain(std::iter::once(text.len()))
+            .collect();
+        let len = bounds.len() - 1;
+
+        let mut nodes = vec![Node {
+            start: 0,
+            end: 0,
+            right_id: BOS_EOS_ID,
+            features: "",
+            total: 0,
+            prev: 0,
+        }];
+        // 各文字の位置で終わるノードの番号
+        let mut ends: Vec<Vec<usize>> = vec![Vec::new(); len + 1];
+        ends[0].push(0);
+
+        let mut candidates = Vec::new();
+        for start in 0..len {
+            if ends[start].is_empty() {
+                continue;
+            }
+            candidates.clear();
+            self.lookup(text, &bounds, start, &mut candidates);
+
+            for &(end, entry) in &candidates {
+                let (prev, total) = ends[start]
+                    .iter()
+                    .map(|&p| {
+                        let cost = nodes[p].total
+                            + i64::from(self.connection_cost(nodes[p].right_id, entry.left_id));
+                        (p, cost)
+                    })
+                    .min_by_key(|&(_, cost)| cost)
+                    .unwrap();
+                ends[end].push(nodes.len());
+                nodes.push(Node {
+                    start,
+                    end,
+                    right_id: entry.right_id,
+                    features: &entry.features,
+                    total: total + i64::from(entry.cost),
+                    prev,
+                });
+            }
+        }
+
+        let mut best = ends[len]
+            .iter()
+            .copied()
+            .min_by_key(|&p| {
+                nodes[p].total + i64::from(self.connection_cost(nodes[p].right_id, BOS_EOS_ID))
+            })
+            .expect("every position has an unknown word candidate");
+        let first = morphemes.len();
+        while best != 0 {
+            let node = &nodes[best];
+            morphemes.push(Morpheme {
+                surface: &text[bounds[node.start]..bounds[node.end]],
+                features: node.features,
+            });
+            best = node.prev;
+        }
+        morphemes[first..].reverse();
+    }
+
+    /// `start`の位置から始まる語の候補を、終わりの位置とともに`candidates`に追加する。
+    fn lookup<'a>(
+        &'a self,
+        text: &str,
+        bounds: &[usize],
+        start: usize,
+        candidates: &mut Vec<(usize, &'a Entry)>,
+    ) {
+        let len = bounds.len() - 1;
+        for end in start + 1..=len.min(start + self.max_chars) {
+            if let Some(entries) = self.entries.get(&text[bounds[start]..bounds[end]]) {
+                candidates.extend(entries.iter().map(|e| (end, e)));
+            }
+        }
+
+        let class = CharClass::of(text[bounds[start]..].chars().next().unwrap());
+        if !candidates.is_empty() && !class.invoke() {
+            return;
+        }
+        let entries = self
+            .unknown
+            .get(&class)
+            .unwrap_or(&self.unknown[&CharClass::Default]);
+        // 同じ文字種が続く範囲
+        let run = text[bounds[start]..]
+            .chars()
+            .take_while(|&c| CharClass::of(c) == class)
+            .count();
+        let mut lengths: Vec<usize> = (1..=class.length().min(run)).collect();
+        if class.group() || lengths.is_empty() {
+            lengths.push(if class.group() { run } else { 1 });
+        }
+        lengths.dedup();
+        for n in lengths {
+            candidates.extend(entries.iter().map(|e| (start + n, e)));
+        }
+    }
+}
+
+/// ラティスのノード
+struct Node<'a> {
+    /// 語の始まりの文字の位置
+    start: usize,
+    /// 語の終わりの文字の位置
+    end: usize,
+    right_id: u16,
+    features: &'a str,
+    /// 文頭からこのノードまでの最小コスト
+    total: i64,
+    /// 最小コストとなる直前のノードの番号
+    prev: usize,
+}
+
+fn invalid_data(file: &str, line: usize, content: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{}:{}: invalid line: {}", file, line, content),
+    )
+}
+
+/// 未知語の処理に使用する文字種
+///
+/// IPADICの`char.def`の分類に従う。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum CharClass {
+    Default,
+    Space,
+    Kanji,
+    Symbol,
+    Numeric,
+    Alpha,
+    Hiragana,
+    Katakana,
+    KanjiNumeric,
+    Greek,
+    Cyrillic,
+}
+
+impl CharClass {
+    fn from_name(name: &str) -> Option<CharClass> {
+        Some(match name {
+            "DEFAULT" => CharClass::Default,
+            "SPACE" => CharClass::Space,
+            "KANJI" => CharClass::Kanji,
+            "SYMBOL" => CharClass::Symbol,
+            "NUMERIC" => CharClass::Numeric,
+            "ALPHA" => CharClass::Alpha,
+            "HIRAGANA" => CharClass::Hiragana,
+            "KATAKANA" => CharClass::Katakana,
+            "KANJINUMERIC" => CharClass::KanjiNumeric,
+            "GREEK" => CharClass::Greek,
+            "CYRILLIC" => CharClass::Cyrillic,
+            _ => return None,
+        })
+    }
+
+    fn of(c: char) -> CharClass {
+        match c {
+            c if c.is_whitespace() => CharClass::Space,
+            '0'..='9' | '０'..='９' => CharClass::Numeric,
+            'A'..='Z' | 'a'..='z' | 'Ａ'..='Ｚ' | 'ａ'..='ｚ' => CharClass::Alpha,
+            '〇' | '一' | '二' | '三' | '四' | '五' | '六' | '七' | '八' | '九' | '十' | '百'
+            | '千' | '万' | '億' | '兆' => CharClass::KanjiNumeric,
+            '\u{3041}'..='\u{309F}' => CharClass::Hiragana,
+            '\u{30A1}'..='\u{30FF}' | '\u{31F0}'..='\u{31FF}' | '\u{FF66}'..='\u{FF9F}' => {
+                CharClass::Katakana
+            }
+            '々'
+            | '〆'
+            | '\u{3400}'..='\u{4DBF}'
+            | '\u{4E00}'..='\u{9FFF}'
+            | '\u{F900}'..='\u{FAFF}'
+            | '\u{20000}'..='\u{2FFFF}' => CharClass::Kanji,
+            '\u{0370}'..='\u{03FF}' => CharClass::Greek,
+            '\u{0400}'..='\u{04FF}' => CharClass::Cyrillic,
+            c if c.is_ascii_punctuation()
+                || matches!(c, '\u{2000}'..='\u{2BFF}' | '\u{3000}'..='\u{303F}' | '\u{FF01}'..='\u{FF0F}' | '\u{FF1A}'..='\u{FF20}' | '\u{FF3B}'..='\u{FF40}' | '\u{FF5B}'..='\u{FF65}') =>
+            {
+                CharClass::Symbol
+            }
+            _ => CharClass::Default,
+        }
+    }
+
+    /// 辞書に語があっても、未知語の候補を追加するかどうか
+    fn invoke(self) -> bool {
+        matches!(
+            self,
+            CharClass::Symbol
+                | CharClass::Numeric
+                | CharClass::Alpha
+                | CharClass::Katakana
+                | CharClass::KanjiNumeric
+                | CharClass::Greek
+                | CharClass::Cyrillic
+        )
+    }
+
+    /// 同じ文字種が続く範囲を、1つの未知語の候補とするかどうか
+    fn group(self) -> bool {
+        !matches!(self, CharClass::Kanji | CharClass::Hiragana)
+    }
+
+    /// 先頭から何文字までの範囲を、未知語の候補とするか
+    fn length(self) -> usize {
+        match self {
+            CharClass::Kanji | CharClass::Hiragana | CharClass::Katakana => 2,
+            _ => 0,
+        }
+    }
+}
+
+/// 形態素
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Morpheme<'a> {
+    surface: &'a str,
+    features: &'a str,
+}
+
+impl<'a> Morpheme<'a> {
+    /// 表層形（文中に現れた形）を返す。
+    pub fn surface(&self) -> &'a str {
+        self.surface
+    }
+
+    /// 品詞、品詞細分類、活用型、活用形、原形、読み及び発音をカンマで区切った素性を返す。
+    pub fn features(&self) -> &'a str {
+        self.features
+    }
+
+    /// `i`番目の素性を返す。素性が`*`の場合は`None`を返す。
+    fn feature(&self, i: usize) -> Option<&'a str> {
+        self.features.split(',').nth(i).filter(|&f| f != "*")
+    }
+
+    /// IPADICの品詞名（`名詞`など）を返す。
+    pub fn pos(&self) -> &'a str {
+        self.feature(0).unwrap_or("")
+    }
+
+    /// 品詞を返す。
+    pub fn part_of_speech(&self) -> PartOfSpeech {
+        PartOfSpeech::from_ipadic(self.pos()).unwrap_or(PartOfSpeech::Other)
+    }
+
+    /// 原形（辞書に記載されている形）を返す。原形が不明な場合は、表層形を返す。
+    pub fn base_form(&self) -> &'a str {
+        self.feature(6).unwrap_or(self.surface)
+    }
+
+    /// 読みを返す。
+    pub fn reading(&self) -> Option<&'a str> {
+        self.feature(7)
+    }
+}
+
+/// 品詞
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PartOfSpeech {
+    /// 名詞
+    Noun,
+    /// 動詞
+    Verb,
+    /// 形容詞
+    Adjective,
+    /// 副詞
+    Adverb,
+    /// 連体詞
+    Adnominal,
+    /// 接続詞
+    Conjunction,
+    /// 感動詞
+    Interjection,
+    /// 助詞
+    Particle,
+    /// 助動詞
+    AuxiliaryVerb,
+    /// 接頭詞
+    Prefix,
+    /// 記号
+    Symbol,
+    /// フィラー
+    Filler,
+    /// その他
+    Other,
+}
+
+impl PartOfSpeech {
+    const ALL: [PartOfSpeech; 13] = [
+        PartOfSpeech::Noun,
+        PartOfSpeech::Verb,
+        PartOfSpeech::Adjective,
+        PartOfSpeech::Adverb,
+        PartOfSpeech::Adnominal,
+        PartOfSpeech::Conjunction,
+        PartOfSpeech::Interjection,
+        PartOfSpeech::Particle,
+        PartOfSpeech::AuxiliaryVerb,
+        PartOfSpeech::Prefix,
+        PartOfSpeech::Symbol,
+        PartOfSpeech::Filler,
+        PartOfSpeech::Other,
+    ];
+
+    /// IPADICの品詞名から品詞を返す。
+    pub fn from_ipadic(name: &str) -> Option<PartOfSpeech> {
+        PartOfSpeech::ALL
+            .iter()
+            .copied()
+            .find(|pos| pos.ipadic_name() == name)
+    }
+
+    /// IPADICの品詞名を返す。
+    pub fn ipadic_name(&self) -> &'static str {
+        match self {
+            PartOfSpeech::Noun => "名詞",
+            PartOfSpeech::Verb => "動詞",
+            PartOfSpeech::Adjective => "形容詞",
+            PartOfSpeech::Adverb => "副詞",
+            PartOfSpeech::Adnominal => "連体詞",
+            PartOfSpeech::Conjunction => "接続詞",
+            PartOfSpeech::Interjection => "感動詞",
+            PartOfSpeech::Particle => "助詞",
+            PartOfSpeech::AuxiliaryVerb => "助動詞",
+            PartOfSpeech::Prefix => "接頭詞",
+            PartOfSpeech::Symbol => "記号",
+            PartOfSpeech::Filler => "フィラー",
+            PartOfSpeech::Other => "その他",
+        }
+    }
+
+    /// 英語の品詞名を返す。
+    fn english_name(&self) -> &'static str {
+        match self {
+            PartOfSpeech::Noun => "noun",
+            PartOfSpeech::Verb => "verb",
+            PartOfSpeech::Adjective => "adjective",
+            PartOfSpeech::Adverb => "adverb",
+            PartOfSpeech::Adnominal => "adnominal",
+            PartOfSpeech::Conjunction => "conjunction",
+            PartOfSpeech::Interjection => "interjection",
+            PartOfSpeech::Particle => "particle",
+            PartOfSpeech::AuxiliaryVerb => "auxiliary-verb",
+            PartOfSpeech::Prefix => "prefix",
+            PartOfSpeech::Symbol => "symbol",
+            PartOfSpeech::Filler => "filler",
+            PartOfSpeech::Other => "other",
+        }
+    }
+
+    fn bit(self) -> u16 {
+        1 << (self as u16)
+    }
+}
+
+impl fmt::Display for PartOfSpeech {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.ipadic_name())
+    }
+}
+
+/// IPADICの品詞名（`名詞`）と英語の品詞名（`noun`）のどちらからでも変換できる。
+impl FromStr for PartOfSpeech {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        PartOfSpeech::ALL
+            .iter()
+            .copied()
+            .find(|pos| pos.ipadic_name() == s || pos.english_name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("不明な品詞です: {}", s))
+    }
+}
+
+/// 品詞の集合
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PosSet(u16);
+
+impl PosSet {
+    /// 全ての品詞を含む集合
+    pub const ALL: PosSet = PosSet((1 << PartOfSpeech::ALL.len()) - 1);
+
+    /// 空の集合を返す。
+    pub fn empty() -> PosSet {
+        PosSet(0)
+    }
+
+    /// `pos`を加えた集合を返す。
+    pub fn with(self, pos: PartOfSpeech) -> PosSet {
+        PosSet(self.0 | pos.bit())
+    }
+
+    /// 集合に`pos`が含まれるかどうかを返す。
+    pub fn contains(self, pos: PartOfSpeech) -> bool {
+        self.0 & pos.bit() != 0
+    }
+}
+
+impl Default for PosSet {
+    fn default() -> Self {
+        PosSet::ALL
+    }
+}
+
+impl FromIterator<PartOfSpeech> for PosSet {
+    fn from_iter<I: IntoIterator<Item = PartOfSpeech>>(iter: I) -> Self {
+        iter.into_iter().fold(PosSet::empty(), PosSet::with)
+    }
+}
+
+/// [`CountOption::Morpheme`](../enum.CountOption.html#variant.Morpheme)で使用するオプション
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct MorphemeOption {
+    /// 表層形の代わりに原形を数える。
+    pub base_form: bool,
+    /// `東京/名詞`のように、品詞を付けて数える。
+    pub with_pos: bool,
+    /// 数える品詞
+    pub pos: PosSet,
+}
+
+impl MorphemeOption {
+    /// オプションに従って、形態素を数えるときのキーを`key`に書き込む。
+    ///
+    /// 数える品詞でない場合は`false`を返す。
+    pub(crate) fn key(&self, morpheme: &Morpheme<'_>, key: &mut String) -> bool {
+        if !self.pos.contains(morpheme.part_of_speech()) {
+            return false;
+        }
+        key.clear();
+        key.push_str(if self.base_form {
+            morpheme.base_form()
+        } else {
+            morpheme.surface()
+        });
+        if self.with_pos {
+            key.push('/');
+            key.push_str(morpheme.pos());
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn surfaces(text: &str) -> Vec<&str> {
+        Dictionary::bundled()
+            .tokenize(text)
+            .iter()
+            .map(|m| m.surface())
+            .collect()
+    }
+
+    #[test]
+    fn tokenize_sentences() {
+        assert_eq!(
+            surfaces("すもももももももものうち"),
+            ["すもも", "も", "もも", "も", "もも", "の", "うち"]
+        );
+        assert_eq!(
+            surfaces("私は学生です。"),
+            ["私", "は", "学生", "です", "。"]
+        );
+        assert_eq!(
+            surfaces("今日は良い天気ですね"),
+            ["今日", "は", "良い", "天気", "です", "ね"]
+        );
+    }
+
+    #[test]
+    fn tokenize_unknown_words() {
+        assert_eq!(
+            surfaces("ルビーとRustを使う"),
+            ["ルビー", "と", "Rust", "を", "使う"]
+        );
+        assert_eq!(surfaces("2024年 3月"), ["2024", "年", "3", "月"]);
+    }
+
+    #[test]
+    fn base_form_and_pos() {
+        let dict = Dictionary::bundled();
+        let morphemes = dict.tokenize("日本語を勉強しています");
+        let base: Vec<_> = morphemes.iter().map(|m| m.base_form()).collect();
+        assert_eq!(base, ["日本語", "を", "勉強", "する", "て", "いる", "ます"]);
+        assert_eq!(morphemes[0].part_of_speech(), PartOfSpeech::Noun);
+        assert_eq!(morphemes[3].part_of_speech(), PartOfSpeech::Verb);
+    }
+
+    #[test]
+    fn load_dictionary_from_readers() {
+        let lexicon = "あ,1,1,100,感動詞,*,*,*,*,*,あ,ア,ア\n";
+        let matrix = "2 2\n0 0 0\n0 1 0\n1 0 0\n1 1 0\n";
+        let unknown = "DEFAULT,1,1,1000,記号,一般,*,*,*,*,*\n";
+        let dict =
+            Dictionary::from_readers(lexicon.as_bytes(), matrix.as_bytes(), unknown.as_bytes())
+                .unwrap();
+        let morphemes = dict.tokenize("ああ");
+        assert_eq!(morphemes.len(), 2);
+        assert_eq!(morphemes[0].reading(), Some("ア"));
+
+        let err = Dictionary::from_readers(
+            "い,5,5,100,感動詞\n".as_bytes(),
+            matrix.as_bytes(),
+            unknown.as_bytes(),
+        )
+        .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn parse_part_of_speech() {
+        assert_eq!("名詞".parse(), Ok(PartOfSpeech::Noun));
+        assert_eq!("Verb".parse(), Ok(PartOfSpeech::Verb));
+        let set: PosSet = [PartOfSpeech::Noun, PartOfSpeech::Verb]
+            .into_iter()
+            .collect();
+        assert!(set.contains(PartOfSpeech::Verb));
+        assert!(!set.contains(PartOfSpeech::Particle));
+    }
+}
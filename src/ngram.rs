@@ -0,0 +1,81 @@
+//! n-gramの出現頻度を数えるための補助機能。
+use std::collections::VecDeque;
+
+/// 直近の`n`個のトークンを保持し、n-gramのキーを作成するウィンドウ
+#[derive(Debug, Clone)]
+pub(crate) struct Window {
+    n: usize,
+    separator: String,
+    tokens: VecDeque<String>,
+}
+
+impl Window {
+    /// トークンを`separator`で連結したn-gramを作成するウィンドウを返す。
+    pub(crate) fn new(n: usize, separator: impl Into<String>) -> Self {
+        Window {
+            n,
+            separator: separator.into(),
+            tokens: VecDeque::with_capacity(n),
+        }
+    }
+
+    /// トークンを追加する。
+    ///
+    /// ウィンドウが`n`個のトークンで満たされた場合は、n-gramを`key`に書き込んで`true`を返す。
+    pub(crate) fn push(&mut self, token: &str, key: &mut String) -> bool {
+        if self.n == 0 {
+            return false;
+        }
+        // 取り出したトークンの領域を再利用する。
+        let mut slot = if self.tokens.len() == self.n {
+            self.tokens.pop_front().unwrap()
+        } else {
+            String::new()
+        };
+        slot.clear();
+        slot.push_str(token);
+        self.tokens.push_back(slot);
+        if self.tokens.len() < self.n {
+            return false;
+        }
+
+        key.clear();
+        for (i, token) in self.tokens.iter().enumerate() {
+            if i > 0 {
+                key.push_str(&self.separator);
+            }
+            key.push_str(token);
+        }
+        true
+    }
+
+    /// 保持しているトークンを捨てる。
+    pub(crate) fn clear(&mut self) {
+        self.tokens.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn window_makes_ngrams() {
+        let mut window = Window::new(2, " ");
+        let mut key = String::new();
+        assert!(!window.push("a", &mut key));
+        assert!(window.push("b", &mut key));
+        assert_eq!(key, "a b");
+        assert!(window.push("c", &mut key));
+        assert_eq!(key, "b c");
+        window.clear();
+        assert!(!window.push("d", &mut key));
+    }
+
+    #[test]
+    fn zero_sized_window_makes_nothing() {
+        let mut window = Window::new(0, "");
+        let mut key = String::new();
+        assert!(!window.push("a", &mut key));
+    }
+}
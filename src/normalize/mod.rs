@@ -0,0 +1,332 @@
+//! トークンを数える前に、表記の揺れを正規化する機能を提供する。
+//!
+//! [`Normalizer`](struct.Normalizer.html)は、次の変換を指定された順に適用する。
+//!
+//! 1. 幅の正規化: 全角英数字・記号を半角に、半角カタカナを全角に変換する。
+//! 2. Unicode正規化: NFCまたはNFKCに変換する。
+//! 3. かなの統一: カタカナを平仮名に、または平仮名をカタカナに変換する。
+//! 4. ケースフォールディング: 大文字と小文字の区別をなくす。
+//!
+//! ```
+//! use kuroyasu_bicycle_book_wordcount::normalize::{Normalizer, UnicodeForm};
+//!
+//! let normalizer = Normalizer {
+//!     case_fold: true,
+//!     form: Some(UnicodeForm::Nfkc),
+//!     ..Default::default()
+//! };
+//! for word in ["Rust", "rust", "ＲＵＳＴ", "ｒｕｓｔ"] {
+//!     assert_eq!(normalizer.normalize(word), "rust");
+//! }
+//! ```
+use std::borrow::Cow;
+use std::cmp::Ordering;
+
+mod tables;
+
+/// Unicode正規化の形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UnicodeForm {
+    /// 正準等価による合成（NFC）
+    Nfc,
+    /// 互換等価による合成（NFKC）
+    Nfkc,
+}
+
+/// かなを統一するときの変換先
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Kana {
+    /// カタカナを平仮名に変換する。
+    Hiragana,
+    /// 平仮名をカタカナに変換する。
+    Katakana,
+}
+
+/// トークンの正規化の設定
+///
+/// 既定値は、何も変換しない。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Normalizer {
+    /// Unicodeのケースフォールディングを適用する。
+    pub case_fold: bool,
+    /// Unicode正規化の形式
+    pub form: Option<UnicodeForm>,
+    /// 全角英数字・記号を半角に、半角カタカナを全角に変換する。
+    pub width: bool,
+    /// 平仮名とカタカナを統一する。
+    pub kana: Option<Kana>,
+}
+
+impl Normalizer {
+    /// 何も変換しない設定であれば`true`を返す。
+    pub fn is_identity(&self) -> bool {
+        *self == Normalizer::default()
+    }
+
+    /// `s`を正規化した文字列を返す。
+    pub fn normalize(&self, s: &str) -> String {
+        let mut out = String::new();
+        self.normalize_into(s, &mut out);
+        out
+    }
+
+    /// `s`を正規化した文字列を`out`に書き込む。`out`の内容は上書きされる。
+    pub fn normalize_into(&self, s: &str, out: &mut String) {
+        out.clear();
+        let mut s = Cow::Borrowed(s);
+        if self.width {
+            s = Cow::Owned(normalize_width(&s));
+        }
+        if let Some(form) = self.form {
+            if !s.is_ascii() {
+                s = Cow::Owned(normalize_form(&s, form));
+            }
+        }
+        if let Some(kana) = self.kana {
+            s = Cow::Owned(s.chars().map(|c| convert_kana(c, kana)).collect());
+        }
+        if self.case_fold {
+            for c in s.chars() {
+                match lookup(&tables::CASE_FOLDING, c) {
+                    Some(folded) => out.push_str(folded),
+                    None => out.push(c),
+                }
+            }
+        } else {
+            out.push_str(&s);
+        }
+    }
+}
+
+/// `U+FF61`から`U+FF9D`までの半角カタカナに対応する全角の文字
+const HALFWIDTH_KATAKANA: [char; 61] = [
+    '。', '「', '」', '、', '・', 'ヲ', 'ァ', 'ィ', 'ゥ', 'ェ', 'ォ', 'ャ', 'ュ', 'ョ', 'ッ', 'ー',
+    'ア', 'イ', 'ウ', 'エ', 'オ', 'カ', 'キ', 'ク', 'ケ', 'コ', 'サ', 'シ', 'ス', 'セ', 'ソ', 'タ',
+    'チ', 'ツ', 'テ', 'ト', 'ナ', 'ニ', 'ヌ', 'ネ', 'ノ', 'ハ', 'ヒ', 'フ', 'ヘ', 'ホ', 'マ', 'ミ',
+    'ム', 'メ', 'モ', 'ヤ', 'ユ', 'ヨ', 'ラ', 'リ', 'ル', 'レ', 'ロ', 'ワ', 'ン',
+];
+
+/// 全角英数字・記号を半角に、半角カタカナを全角に変換する。
+///
+/// 半角の濁点及び半濁点は、直前のカタカナと合成する。合成できない場合は、全角の濁点及び半濁点にする。
+fn normalize_width(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\u{FF01}'..='\u{FF5E}' => out.push(char::from_u32(c as u32 - 0xFEE0).unwrap()),
+            '\u{3000}' => out.push(' '),
+            '\u{FF61}'..='\u{FF9D}' => out.push(HALFWIDTH_KATAKANA[(c as u32 - 0xFF61) as usize]),
+            '\u{FF9E}' | '\u{FF9F}' => {
+                let (mark, spacing) = if c == '\u{FF9E}' {
+                    ('\u{3099}', '\u{309B}')
+                } else {
+                    ('\u{309A}', '\u{309C}')
+                };
+                match out.chars().last().and_then(|last| compose_pair(last, mark)) {
+                    Some(composed) => {
+                        out.pop();
+                        out.push(composed);
+                    }
+                    None => out.push(spacing),
+                }
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn convert_kana(c: char, kana: Kana) -> char {
+    let c = c as u32;
+    let converted = match kana {
+        Kana::Hiragana if (0x30A1..=0x30F6).contains(&c) || c == 0x30FD || c == 0x30FE => c - 0x60,
+        Kana::Katakana if (0x3041..=0x3096).contains(&c) || c == 0x309D || c == 0x309E => c + 0x60,
+        _ => c,
+    };
+    char::from_u32(converted).unwrap()
+}
+
+/// `s`をNFCまたはNFKCに正規化する。
+fn normalize_form(s: &str, form: UnicodeForm) -> String {
+    let mut chars = Vec::with_capacity(s.len());
+    decompose(s, form == UnicodeForm::Nfkc, &mut chars);
+    compose(&mut chars);
+    chars.into_iter().collect()
+}
+
+const HANGUL_S_BASE: u32 = 0xAC00;
+const HANGUL_L_BASE: u32 = 0x1100;
+const HANGUL_V_BASE: u32 = 0x1161;
+const HANGUL_T_BASE: u32 = 0x11A7;
+const HANGUL_L_COUNT: u32 = 19;
+const HANGUL_V_COUNT: u32 = 21;
+const HANGUL_T_COUNT: u32 = 28;
+const HANGUL_N_COUNT: u32 = HANGUL_V_COUNT * HANGUL_T_COUNT;
+const HANGUL_S_COUNT: u32 = HANGUL_L_COUNT * HANGUL_N_COUNT;
+
+/// `s`を完全に分解し、結合文字を正準順序に並べ替えて`out`に追加する。
+fn decompose(s: &str, compatibility: bool, out: &mut Vec<char>) {
+    for c in s.chars() {
+        let s_index = (c as u32).wrapping_sub(HANGUL_S_BASE);
+        if s_index < HANGUL_S_COUNT {
+            let l = HANGUL_L_BASE + s_index / HANGUL_N_COUNT;
+            let v = HANGUL_V_BASE + (s_index % HANGUL_N_COUNT) / HANGUL_T_COUNT;
+            let t = HANGUL_T_BASE + s_index % HANGUL_T_COUNT;
+            out.push(char::from_u32(l).unwrap());
+            out.push(char::from_u32(v).unwrap());
+            if t != HANGUL_T_BASE {
+                out.push(char::from_u32(t).unwrap());
+            }
+            continue;
+        }
+        let decomposition = compatibility
+            .then(|| lookup(&tables::COMPATIBILITY_DECOMPOSITION, c))
+            .flatten()
+            .or_else(|| lookup(&tables::CANONICAL_DECOMPOSITION, c));
+        match decomposition {
+            Some(d) => out.extend(d.chars()),
+            None => out.push(c),
+        }
+    }
+
+    // 連続する結合文字を、結合クラスの順に安定ソートする。
+    let mut start = 0;
+    while start < out.len() {
+        if combining_class(out[start]) == 0 {
+            start += 1;
+            continue;
+        }
+        let end = out[start..]
+            .iter()
+            .position(|&c| combining_class(c) == 0)
+            .map_or(out.len(), |n| start + n);
+        out[start..end].sort_by_key(|&c| combining_class(c));
+        start = end;
+    }
+}
+
+/// 正準順序に並んだ文字列を、正準合成する。
+fn compose(chars: &mut Vec<char>) {
+    let mut out: Vec<char> = Vec::with_capacity(chars.len());
+    let mut starter: Option<usize> = None;
+    // 直前の開始文字より後に追加した文字の、最後の結合クラス
+    let mut last_class: Option<u8> = None;
+    for &c in chars.iter() {
+        let class = combining_class(c);
+        if let Some(i) = starter {
+            let blocked = last_class.is_some_and(|last| last >= class);
+            if !blocked {
+                if let Some(composed) = compose_pair(out[i], c) {
+                    out[i] = composed;
+                    continue;
+                }
+            }
+        }
+        if class == 0 {
+            starter = Some(out.len());
+            last_class = None;
+        } else {
+            last_class = Some(class);
+        }
+        out.push(c);
+    }
+    *chars = out;
+}
+
+/// 2文字を正準合成した文字を返す。
+fn compose_pair(first: char, second: char) -> Option<char> {
+    let (a, b) = (first as u32, second as u32);
+    let l_index = a.wrapping_sub(HANGUL_L_BASE);
+    let v_index = b.wrapping_sub(HANGUL_V_BASE);
+    if l_index < HANGUL_L_COUNT && v_index < HANGUL_V_COUNT {
+        return char::from_u32(
+            HANGUL_S_BASE + (l_index * HANGUL_V_COUNT + v_index) * HANGUL_T_COUNT,
+        );
+    }
+    let s_index = a.wrapping_sub(HANGUL_S_BASE);
+    let t_index = b.wrapping_sub(HANGUL_T_BASE);
+    if s_index < HANGUL_S_COUNT
+        && s_index % HANGUL_T_COUNT == 0
+        && 0 < t_index
+        && t_index < HANGUL_T_COUNT
+    {
+        return char::from_u32(a + t_index);
+    }
+
+    tables::COMPOSITION
+        .binary_search_by(|&(x, y, _)| (x, y).cmp(&(first, second)))
+        .ok()
+        .map(|i| tables::COMPOSITION[i].2)
+}
+
+fn combining_class(c: char) -> u8 {
+    let c = c as u32;
+    tables::COMBINING_CLASS
+        .binary_search_by(|&(start, end, _)| {
+            if end < c {
+                Ordering::Less
+            } else if start > c {
+                Ordering::Greater
+            } else {
+                Ordering::Equal
+            }
+        })
+        .map_or(0, |i| tables::COMBINING_CLASS[i].2)
+}
+
+fn lookup(table: &'static [(char, &'static str)], c: char) -> Option<&'static str> {
+    table
+        .binary_search_by_key(&c, |&(key, _)| key)
+        .ok()
+        .map(|i| table[i].1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unicode_forms() {
+        let nfc = Normalizer {
+            form: Some(UnicodeForm::Nfc),
+            ..Default::default()
+        };
+        let nfkc = Normalizer {
+            form: Some(UnicodeForm::Nfkc),
+            ..Default::default()
+        };
+        assert_eq!(nfc.normalize("か\u{3099}"), "が");
+        assert_eq!(nfc.normalize("e\u{301}\u{323}"), "\u{1EB9}\u{301}");
+        assert_eq!(nfc.normalize("\u{1112}\u{1161}\u{11AB}"), "한");
+        assert_eq!(nfc.normalize("ｶﾞ①"), "ｶﾞ①");
+        assert_eq!(nfkc.normalize("ｶﾞ①ﬁ㍻"), "ガ1fi平成");
+    }
+
+    #[test]
+    fn width() {
+        let normalizer = Normalizer {
+            width: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            normalizer.normalize("Ｒｕｓｔ　ｶﾞｲﾄﾞﾌﾟﾛｸﾞﾗﾑ"),
+            "Rust ガイドプログラム"
+        );
+        assert_eq!(normalizer.normalize("ﾞｱﾟ"), "゛ア゜");
+    }
+
+    #[test]
+    fn kana_and_case_folding() {
+        let normalizer = Normalizer {
+            case_fold: true,
+            kana: Some(Kana::Hiragana),
+            ..Default::default()
+        };
+        assert_eq!(normalizer.normalize("ラスト Straße"), "らすと strasse");
+        let normalizer = Normalizer {
+            kana: Some(Kana::Katakana),
+            ..Default::default()
+        };
+        assert_eq!(normalizer.normalize("らすとゝ"), "ラストヽ");
+    }
+}
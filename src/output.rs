@@ -0,0 +1,430 @@
+//! 結果を、JSONやCSVなどのプログラムで読み取りやすい形式で書き出す機能を提供する。
+//!
+//! 結果は、列の名前と行の並びからなる[`Table`](struct.Table.html)にまとめてから、
+//! [`Format`](enum.Format.html)で指定された形式で書き出す。行は追加した順に書き出すため、
+//! 同じ結果からは常に同じ内容が書き出される。
+use std::error::Error;
+use std::fmt::{self, Write as _};
+use std::io::{self, Write};
+use std::str::FromStr;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::concordance::display_width;
+use crate::Frequencies;
+
+/// 書き出す形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Format {
+    /// 行ごとのオブジェクトを要素とするJSONの配列
+    Json,
+    /// 1行に1つのJSONオブジェクト（NDJSON）
+    Ndjson,
+    /// 1行目を列の名前とするCSV（RFC 4180）
+    Csv,
+    /// 1行目を列の名前とするタブ区切りのテキスト
+    Tsv,
+    /// 列の位置を揃えた表
+    Table,
+}
+
+impl Format {
+    /// `json`や`csv`などの形式の名前を返す。
+    pub fn name(self) -> &'static str {
+        match self {
+            Format::Json => "json",
+            Format::Ndjson => "ndjson",
+            Format::Csv => "csv",
+            Format::Tsv => "tsv",
+            Format::Table => "table",
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Format {
+    type Err = UnknownFormat;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(Format::Json),
+            "ndjson" => Ok(Format::Ndjson),
+            "csv" => Ok(Format::Csv),
+            "tsv" => Ok(Format::Tsv),
+            "table" => Ok(Format::Table),
+            _ => Err(UnknownFormat(s.to_string())),
+        }
+    }
+}
+
+/// 形式の名前が不明であることを示すエラー
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownFormat(String);
+
+impl fmt::Display for UnknownFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "不明な出力形式です: {}", self.0)
+    }
+}
+
+impl Error for UnknownFormat {}
+
+/// 表の1つのセルの値
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// 値がないことを示す。JSONでは`null`、CSVとTSVでは空の文字列、表では`-`とする。
+    Null,
+    /// 整数
+    Integer(usize),
+    /// 浮動小数点数。有限でない値は[`Null`](#variant.Null)と同様に書き出す。
+    Float(f64),
+    /// 文字列
+    Text(String),
+}
+
+impl From<usize> for Value {
+    fn from(n: usize) -> Self {
+        Value::Integer(n)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(x: f64) -> Self {
+        Value::Float(x)
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Value::Text(s)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::Text(s.to_string())
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Value::Null, Into::into)
+    }
+}
+
+/// 列の名前と、行の並び
+///
+/// # Examples
+///
+/// ```
+/// use kuroyasu_bicycle_book_wordcount::output::{Format, Table};
+///
+/// let mut table = Table::new(["key", "count"]);
+/// table.push(vec!["say \"hi\"".into(), 3.into()]);
+/// table.push(vec!["a,b".into(), 1.into()]);
+///
+/// let mut csv = Vec::new();
+/// table.write(Format::Csv, &mut csv).unwrap();
+/// assert_eq!(String::from_utf8(csv).unwrap(), "key,count\n\"say \"\"hi\"\"\",3\n\"a,b\",1\n");
+///
+/// let mut ndjson = Vec::new();
+/// table.write(Format::Ndjson, &mut ndjson).unwrap();
+/// assert_eq!(
+///     String::from_utf8(ndjson).unwrap(),
+///     "{\"key\":\"say \\\"hi\\\"\",\"count\":3}\n{\"key\":\"a,b\",\"count\":1}\n"
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Table {
+    columns: Vec<String>,
+    rows: Vec<Vec<Value>>,
+}
+
+impl Table {
+    /// `columns`を列の名前とする、行のない表を作成する。
+    pub fn new(columns: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        Table {
+            columns: columns.into_iter().map(Into::into).collect(),
+            rows: Vec::new(),
+        }
+    }
+
+    /// 表の末尾に行を追加する。
+    ///
+    /// # Panics
+    ///
+    /// `row`の値の数が列の数と異なる場合は、パニックを起こす。
+    pub fn push(&mut self, row: Vec<Value>) {
+        assert_eq!(
+            row.len(),
+            self.columns.len(),
+            "the number of values differs from the number of columns"
+        );
+        self.rows.push(row);
+    }
+
+    /// 列の名前を返す。
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    /// 追加した順に、行を返す。
+    pub fn rows(&self) -> &[Vec<Value>] {
+        &self.rows
+    }
+
+    /// 行の数を返す。
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// 行がなければ`true`を返す。
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// 表を`format`の形式で`writer`に書き出す。
+    ///
+    /// 文字列は次のようにエスケープする。
+    ///
+    /// - JSONとNDJSON: `"`、`\`及び制御文字をJSONの規則でエスケープする。
+    /// - CSV: `,`、`"`、`\n`または`\r`を含む値を`"`で囲み、`"`を`""`とする。
+    /// - TSVと表: `\`、タブ、`\n`及び`\r`を、それぞれ`\\`、`\t`、`\n`及び`\r`とする。
+    ///
+    /// 浮動小数点数は、表では小数点以下6桁、それ以外では元の値を復元できる最短の桁数で書き出す。
+    ///
+    /// # Errors
+    ///
+    /// 書き込みに失敗した場合は、エラーを返す。
+    pub fn write(&self, format: Format, mut writer: impl Write) -> io::Result<()> {
+        match format {
+            Format::Json => {
+                if self.rows.is_empty() {
+                    writeln!(writer, "[]")?;
+                } else {
+                    writeln!(writer, "[")?;
+                    for (i, row) in self.rows.iter().enumerate() {
+                        let separator = if i + 1 < self.rows.len() { "," } else { "" };
+                        writeln!(writer, "  {}{}", self.json_object(row), separator)?;
+                    }
+                    writeln!(writer, "]")?;
+                }
+            }
+            Format::Ndjson => {
+                for row in &self.rows {
+                    writeln!(writer, "{}", self.json_object(row))?;
+                }
+            }
+            Format::Csv => {
+                let header: Vec<_> = self.columns.iter().map(|c| csv_field(c)).collect();
+                writeln!(writer, "{}", header.join(","))?;
+                for row in &self.rows {
+                    let fields: Vec<_> = row
+                        .iter()
+                        .map(|value| match value {
+                            Value::Text(s) => csv_field(s),
+                            _ => tsv_field(value),
+                        })
+                        .collect();
+                    writeln!(writer, "{}", fields.join(","))?;
+                }
+            }
+            Format::Tsv => {
+                let header: Vec<_> = self
+                    .columns
+                    .iter()
+                    .map(|c| crate::index::escape(c))
+                    .collect();
+                writeln!(writer, "{}", header.join("\t"))?;
+                for row in &self.rows {
+                    let fields: Vec<_> = row.iter().map(tsv_field).collect();
+                    writeln!(writer, "{}", fields.join("\t"))?;
+                }
+            }
+            Format::Table => self.write_aligned(&mut writer)?,
+        }
+        writer.flush()
+    }
+
+    /// `row`を、列の名前をキーとするJSONオブジェクトにする。
+    fn json_object(&self, row: &[Value]) -> String {
+        let mut object = String::from("{");
+        for (i, (column, value)) in self.columns.iter().zip(row).enumerate() {
+            if i > 0 {
+                object.push(',');
+            }
+            json_string(&mut object, column);
+            object.push(':');
+            match value {
+                Value::Text(s) => json_string(&mut object, s),
+                _ => object.push_str(&plain(value)),
+            }
+        }
+        object.push('}');
+        object
+    }
+
+    /// 列の幅を揃えて書き出す。数値を含む列は右に、それ以外の列は左に揃える。
+    fn write_aligned(&self, writer: &mut impl Write) -> io::Result<()> {
+        let header: Vec<_> = self
+            .columns
+            .iter()
+            .map(|c| crate::index::escape(c))
+            .collect();
+        let cells: Vec<Vec<String>> = self
+            .rows
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|value| match value {
+                        Value::Integer(n) => n.to_string(),
+                        Value::Float(x) if x.is_finite() => format!("{:.6}", x),
+                        Value::Text(s) => crate::index::escape(s),
+                        Value::Null | Value::Float(_) => "-".to_string(),
+                    })
+                    .collect()
+            })
+            .collect();
+        let columns: Vec<(usize, bool)> = (0..self.columns.len())
+            .map(|i| {
+                let width = std::iter::once(&header)
+                    .chain(&cells)
+                    .map(|row| display_width(&row[i]))
+                    .max()
+                    .unwrap_or(0);
+                let numeric = self.rows.iter().any(|row| match row[i] {
+                    Value::Integer(_) => true,
+                    Value::Float(x) => x.is_finite(),
+                    _ => false,
+                });
+                (width, numeric)
+            })
+            .collect();
+        for row in std::iter::once(&header).chain(&cells) {
+            let mut line = String::new();
+            for (i, (cell, &(width, numeric))) in row.iter().zip(&columns).enumerate() {
+                if i > 0 {
+                    line.push_str("  ");
+                }
+                let padding = " ".repeat(width - display_width(cell));
+                if numeric {
+                    line.push_str(&padding);
+                    line.push_str(cell);
+                } else {
+                    line.push_str(cell);
+                    line.push_str(&padding);
+                }
+            }
+            writeln!(writer, "{}", line.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+/// トークンと出現頻度の表を作成する。行は[`Frequencies::sorted_by_count`](../struct.Frequencies.html#method.sorted_by_count)
+/// と同じく、出現頻度が高い順、同じ場合は辞書順に並べる。
+impl From<&Frequencies> for Table {
+    fn from(freqs: &Frequencies) -> Self {
+        let mut table = Table::new(["key", "count"]);
+        for (key, count) in freqs.sorted_by_count() {
+            table.push(vec![key.into(), count.into()]);
+        }
+        table
+    }
+}
+
+/// 文字列以外の値を、JSONの値として書き出す文字列にする。`Null`は`null`とする。
+fn plain(value: &Value) -> String {
+    match value {
+        Value::Integer(n) => n.to_string(),
+        Value::Float(x) if x.is_finite() => x.to_string(),
+        Value::Text(s) => s.clone(),
+        Value::Null | Value::Float(_) => "null".to_string(),
+    }
+}
+
+/// `s`をJSONの文字列として`out`に追加する。
+fn json_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\u{8}' => out.push_str("\\b"),
+            '\u{c}' => out.push_str("\\f"),
+            c if c < ' ' => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// `s`をCSVのフィールドにする。
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+/// `value`をCSVまたはTSVのフィールドにする。値がない場合は空の文字列とする。
+fn tsv_field(value: &Value) -> String {
+    match value {
+        Value::Text(s) => crate::index::escape(s),
+        Value::Null => String::new(),
+        Value::Float(x) if !x.is_finite() => String::new(),
+        _ => plain(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn written(table: &Table, format: Format) -> String {
+        let mut buffer = Vec::new();
+        table.write(format, &mut buffer).unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+
+    #[test]
+    fn json_escapes() {
+        let mut s = String::new();
+        json_string(&mut s, "a\"b\\c\n\u{1}あ");
+        assert_eq!(s, r#""a\"b\\c\n\u0001あ""#);
+    }
+
+    #[test]
+    fn missing_values() {
+        let mut table = Table::new(["name", "value"]);
+        table.push(vec!["none".into(), Value::Null]);
+        table.push(vec!["nan".into(), f64::NAN.into()]);
+        assert_eq!(
+            written(&table, Format::Ndjson),
+            "{\"name\":\"none\",\"value\":null}\n{\"name\":\"nan\",\"value\":null}\n"
+        );
+        assert_eq!(written(&table, Format::Csv), "name,value\nnone,\nnan,\n");
+        assert_eq!(
+            written(&table, Format::Table),
+            "name  value\nnone  -\nnan   -\n"
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn row_length_mismatch() {
+        Table::new(["a", "b"]).push(vec![1.into()]);
+    }
+}
@@ -0,0 +1,210 @@
+//! 大きなファイルを複数のスレッドで分担して、出現頻度を数える機能を提供する。
+//!
+//! ファイルを改行の直後で区切ったバイト範囲に分割し、範囲ごとに別のスレッドで数えた出現頻度を
+//! 合算する。ファイルはUTF-8で記録されている必要がある。
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::thread;
+
+use crate::tokenizer::{LineOption, Tokenizer};
+use crate::{CountError, CountOption};
+
+/// `path`のファイルを`jobs`個のスレッドで分担して、出現頻度を数える。
+///
+/// 結果は、ファイル全体を[`count`](../fn.count.html)で数えた場合と同じになる。
+/// ただし、行をまたぐn-gram、大文字と小文字を区別しない行及びバイトを数える場合は、
+/// 1つのスレッドで数える。
+///
+/// # Panics
+///
+/// ファイルの読み込みに失敗した場合、またはファイルがUTF-8で記録されていない場合は、パニックを起こす。
+/// パニックを避けたい場合は、[`try_count_file`](fn.try_count_file.html)を使用すること。
+pub fn count_file(
+    path: impl AsRef<Path>,
+    option: CountOption,
+    jobs: usize,
+) -> HashMap<String, usize> {
+    try_count_file(path, option, jobs).unwrap_or_else(|e| panic!("{}", e))
+}
+
+/// [`count_file`](fn.count_file.html)と同様に出現頻度を数えるが、パニックを起こす代わりにエラーを返す。
+///
+/// # Errors
+///
+/// [`try_count`](../fn.try_count.html)と同じ条件でエラーを返す。エラーの行番号とバイト位置は、
+/// ファイルの先頭から数える。ファイルを開けなかった場合は、1行目の読み込みに失敗したエラーを返す。
+pub fn try_count_file(
+    path: impl AsRef<Path>,
+    option: CountOption,
+    jobs: usize,
+) -> Result<HashMap<String, usize>, CountError> {
+    if let CountOption::Byte | CountOption::ByteNgram { .. } = option {
+        let file = File::open(path).map_err(|source| CountError::Io {
+            line: 1,
+            offset: 0,
+            source,
+        })?;
+        return crate::try_count(BufReader::new(file), option);
+    }
+    try_count_file_with(path, effective_jobs(option, jobs), || option.tokenizer())
+}
+
+/// `option`で数える場合に、分担するスレッドの数を返す。
+///
+/// 行をまたぐn-gramと大文字と小文字を区別しない行は、先頭から順に数える必要があるため`1`を返す。
+/// それ以外は`jobs`を返す。[`try_count_file_with`](fn.try_count_file_with.html)に
+/// `option`のトークナイザーを加工して渡す場合は、この関数でスレッドの数を求めること。
+pub fn effective_jobs(option: CountOption, jobs: usize) -> usize {
+    match option {
+        CountOption::CharNgram {
+            cross_lines: true, ..
+        }
+        | CountOption::WordNgram {
+            cross_lines: true, ..
+        } => 1,
+        // 最初に出現した行をキーとするため、先頭から順に数える。
+        CountOption::LineWith(LineOption {
+            ignore_case: true, ..
+        }) => 1,
+        _ => jobs,
+    }
+}
+
+/// `path`のファイルを`jobs`個のスレッドで分担して、`make_tokenizer`が作成したトークナイザーが
+/// 取り出したトークンの出現頻度を数える。
+///
+/// トークナイザーはスレッドごとに作成する。各スレッドは担当する範囲の行だけを読み込むため、
+/// 行をまたいで状態を保持するトークナイザーでは、1つのスレッドで数えた場合と結果が異なる。
+///
+/// # Errors
+///
+/// [`try_count_file`](fn.try_count_file.html)と同じ条件でエラーを返す。
+///
+/// # Examples
+///
+/// ```no_run
+/// use kuroyasu_bicycle_book_wordcount::parallel::try_count_file_with;
+/// use kuroyasu_bicycle_book_wordcount::tokenizer::WordTokenizer;
+///
+/// let freqs = try_count_file_with("access.log", 8, || WordTokenizer).unwrap();
+/// println!("{:?}", freqs);
+/// ```
+pub fn try_count_file_with<T, F>(
+    path: impl AsRef<Path>,
+    jobs: usize,
+    make_tokenizer: F,
+) -> Result<HashMap<String, usize>, CountError>
+where
+    T: Tokenizer,
+    F: Fn() -> T + Sync,
+{
+    let path = path.as_ref();
+    let ranges = split(path, jobs).map_err(|source| CountError::Io {
+        line: 1,
+        offset: 0,
+        source,
+    })?;
+
+    let make_tokenizer = &make_tokenizer;
+    let results: Vec<_> = thread::scope(|s| {
+        let handles: Vec<_> = ranges
+            .iter()
+            .map(|&(start, end)| s.spawn(move || count_range(path, start, end, make_tokenizer())))
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|e| std::panic::resume_unwind(e))
+            })
+            .collect()
+    });
+
+    let mut freqs = HashMap::new();
+    // 先に数えた範囲の行数とバイト数を加えて、ファイルの先頭からの位置を報告する。
+    let mut lines = 0;
+    for (result, &(start, _)) in results.into_iter().zip(&ranges) {
+        let (range_freqs, range_lines) = result.map_err(|e| e.shifted(lines, start as usize))?;
+        merge(&mut freqs, range_freqs);
+        lines += range_lines;
+    }
+    Ok(freqs)
+}
+
+/// ファイルを、改行の直後で区切った最大`jobs`個のバイト範囲に分割する。UTF-8のBOMは範囲に含めない。
+fn split(path: &Path, jobs: usize) -> io::Result<Vec<(u64, u64)>> {
+    let file = File::open(path)?;
+    let len = file.metadata()?.len();
+    let jobs = jobs.max(1) as u64;
+    let mut reader = BufReader::new(file);
+    let mut buf = Vec::new();
+
+    // `count`と同じく、先頭のBOMは数えない。
+    let mut bom = [0; 3];
+    let mut start = match reader.read_exact(&mut bom) {
+        Ok(()) if bom == *b"\xEF\xBB\xBF" => 3,
+        _ => 0,
+    };
+    let mut ranges = Vec::new();
+    for i in 1..jobs {
+        let target = len * i / jobs;
+        if target <= start {
+            continue;
+        }
+        // 直前のバイトから読み込み、`target`以降で最初の行頭を求める。
+        reader.seek(SeekFrom::Start(target - 1))?;
+        buf.clear();
+        let end = target - 1 + reader.read_until(b'\n', &mut buf)? as u64;
+        if end >= len {
+            break;
+        }
+        ranges.push((start, end));
+        start = end;
+    }
+    ranges.push((start, len));
+    Ok(ranges)
+}
+
+/// ファイルの`start`から`end`までのバイト範囲を数え、出現頻度と読み込んだ行数を返す。
+///
+/// エラーの行番号とバイト位置は、範囲の先頭から数える。
+fn count_range(
+    path: &Path,
+    start: u64,
+    end: u64,
+    mut tokenizer: impl Tokenizer,
+) -> Result<(HashMap<String, usize>, usize), CountError> {
+    let open = || -> io::Result<_> {
+        let mut file = File::open(path)?;
+        file.seek(SeekFrom::Start(start))?;
+        Ok(BufReader::new(file.take(end - start)))
+    };
+    let input = open().map_err(|source| CountError::Io {
+        line: 1,
+        offset: 0,
+        source,
+    })?;
+
+    let mut freqs = HashMap::new();
+    let mut lines = 0;
+    let mut emit = |token: &str| crate::increment(&mut freqs, token);
+    crate::for_each_line(input, |line| {
+        lines += 1;
+        tokenizer.tokenize(line, &mut emit);
+    })?;
+    tokenizer.finish(&mut emit);
+    Ok((freqs, lines))
+}
+
+/// `other`の出現頻度を`freqs`に加える。
+fn merge(freqs: &mut HashMap<String, usize>, mut other: HashMap<String, usize>) {
+    if freqs.len() < other.len() {
+        std::mem::swap(freqs, &mut other);
+    }
+    for (token, count) in other {
+        *freqs.entry(token).or_insert(0) += count;
+    }
+}
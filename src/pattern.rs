@@ -0,0 +1,103 @@
+//! 正規表現で指定されたトークンを数えるためのパターン。
+use regex::Regex;
+use std::error::Error;
+use std::fmt;
+
+/// [`count_pattern`](../fn.count_pattern.html)で数えるトークンを指定する正規表現のパターン
+///
+/// 既定ではマッチした部分全体を数えるが、[`group`](#method.group)または
+/// [`named_group`](#method.named_group)で、特定のキャプチャグループだけを数えることもできる。
+///
+/// ```
+/// use kuroyasu_bicycle_book_wordcount::Pattern;
+///
+/// // ログ中の`ERROR`に続くエラーコードを数えるパターン
+/// let pattern = Pattern::new(r"ERROR\s+(\w+)").unwrap().group(1).unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    regex: Regex,
+    group: usize,
+}
+
+impl Pattern {
+    /// 正規表現`pattern`にマッチした部分全体を数えるパターンを作成する。
+    ///
+    /// # Errors
+    ///
+    /// `pattern`が正規表現として正しくない場合は、エラーを返す。
+    pub fn new(pattern: &str) -> Result<Pattern, PatternError> {
+        let regex = Regex::new(pattern).map_err(PatternError::Regex)?;
+        Ok(Pattern { regex, group: 0 })
+    }
+
+    /// `group`番目のキャプチャグループを数えるように設定する。`0`はマッチした部分全体を表す。
+    ///
+    /// # Errors
+    ///
+    /// 正規表現にそのキャプチャグループがない場合は、エラーを返す。
+    pub fn group(mut self, group: usize) -> Result<Pattern, PatternError> {
+        if group >= self.regex.captures_len() {
+            return Err(PatternError::NoSuchGroup(group.to_string()));
+        }
+        self.group = group;
+        Ok(self)
+    }
+
+    /// `name`という名前のキャプチャグループを数えるように設定する。
+    ///
+    /// # Errors
+    ///
+    /// 正規表現にその名前のキャプチャグループがない場合は、エラーを返す。
+    pub fn named_group(mut self, name: &str) -> Result<Pattern, PatternError> {
+        self.group = self
+            .regex
+            .capture_names()
+            .position(|n| n == Some(name))
+            .ok_or_else(|| PatternError::NoSuchGroup(name.to_string()))?;
+        Ok(self)
+    }
+
+    /// `line`の中で、数える対象となる部分文字列を順に`f`に渡す。
+    ///
+    /// キャプチャグループが指定されていて、そのグループがマッチに関与しなかった場合は、何も渡さない。
+    pub(crate) fn for_each_match<'a>(&self, line: &'a str, mut f: impl FnMut(&'a str)) {
+        if self.group == 0 {
+            self.regex.find_iter(line).for_each(|m| f(m.as_str()));
+        } else {
+            self.regex
+                .captures_iter(line)
+                .filter_map(|caps| caps.get(self.group))
+                .for_each(|m| f(m.as_str()));
+        }
+    }
+}
+
+/// [`Pattern`](struct.Pattern.html)の作成に失敗したことを示すエラー
+#[derive(Debug, Clone)]
+pub enum PatternError {
+    /// 正規表現として正しくない。
+    Regex(regex::Error),
+    /// 指定されたキャプチャグループがない。
+    NoSuchGroup(String),
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PatternError::Regex(e) => write!(f, "正規表現が正しくありません: {}", e),
+            PatternError::NoSuchGroup(group) => {
+                write!(f, "キャプチャグループがありません: {}", group)
+            }
+        }
+    }
+}
+
+impl Error for PatternError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PatternError::Regex(e) => Some(e),
+            PatternError::NoSuchGroup(_) => None,
+        }
+    }
+}
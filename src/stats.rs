@@ -0,0 +1,206 @@
+//! 出現頻度から、語彙の豊かさや偏りを表す統計量を求める機能を提供する。
+//!
+//! どの[`CountOption`](../enum.CountOption.html)で数えた出現頻度でも、トークンを区別せずに
+//! 同じ方法で求める。
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::Frequencies;
+
+/// 出現頻度から求めた語彙の統計量
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use kuroyasu_bicycle_book_wordcount::stats::Statistics;
+/// use kuroyasu_bicycle_book_wordcount::{count, CountOption, Frequencies};
+///
+/// let freqs = Frequencies::from(count(Cursor::new("a b a c"), CountOption::Word));
+/// let stats = Statistics::new(&freqs);
+/// assert_eq!(stats.tokens, 4);
+/// assert_eq!(stats.types, 3);
+/// assert_eq!(stats.entropy, 1.5);
+/// assert_eq!(stats.hapax_legomena, 2);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Statistics {
+    /// トークンの延べ数`N`
+    pub tokens: usize,
+    /// 異なるトークンの数`V`
+    pub types: usize,
+    /// シャノンエントロピー（ビット）
+    pub entropy: f64,
+    /// 異なり数と延べ数の比`V/N`（type-token ratio）
+    pub type_token_ratio: f64,
+    /// 1回だけ出現したトークンの数
+    pub hapax_legomena: usize,
+    /// 2回だけ出現したトークンの数
+    pub dis_legomena: usize,
+    /// YuleのK特性値`10^4 (Σf² - N) / N²`。値が大きいほど、同じトークンが繰り返し現れる。
+    pub yules_k: f64,
+    /// SimpsonのD`Σf(f-1) / N(N-1)`。無作為に選んだ2つのトークンが同じである確率。
+    pub simpsons_d: f64,
+    /// 出現頻度が順位の`-s`乗に比例するとみなして、両対数の最小二乗法で求めたZipfの指数`s`
+    ///
+    /// 異なるトークンが2つ未満の場合は`None`。
+    pub zipf_exponent: Option<f64>,
+}
+
+impl Statistics {
+    /// `freqs`から統計量を求める。
+    ///
+    /// トークンがない場合、比率やエントロピーは`0.0`とする。
+    pub fn new(freqs: &Frequencies) -> Self {
+        let n = freqs.total() as f64;
+        let mut entropy = 0.0;
+        let mut sum_squares = 0.0;
+        let mut sum_pairs = 0.0;
+        let mut hapax_legomena = 0;
+        let mut dis_legomena = 0;
+        for (_, count) in freqs {
+            let f = count as f64;
+            if count > 0 {
+                let p = f / n;
+                entropy -= p * p.log2();
+            }
+            sum_squares += f * f;
+            sum_pairs += f * (f - 1.0);
+            match count {
+                1 => hapax_legomena += 1,
+                2 => dis_legomena += 1,
+                _ => {}
+            }
+        }
+
+        let ratio = |numerator: f64, denominator: f64| {
+            if denominator > 0.0 {
+                numerator / denominator
+            } else {
+                0.0
+            }
+        };
+        Statistics {
+            tokens: freqs.total(),
+            types: freqs.distinct(),
+            entropy,
+            type_token_ratio: ratio(freqs.distinct() as f64, n),
+            hapax_legomena,
+            dis_legomena,
+            yules_k: 1e4 * ratio(sum_squares - n, n * n),
+            simpsons_d: ratio(sum_pairs, n * (n - 1.0)),
+            zipf_exponent: zipf_exponent(freqs),
+        }
+    }
+}
+
+/// 1行に1つずつ、統計量の名前と値を表示する。
+impl fmt::Display for Statistics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "tokens: {}", self.tokens)?;
+        writeln!(f, "types: {}", self.types)?;
+        writeln!(f, "entropy: {:.6}", self.entropy)?;
+        writeln!(f, "type_token_ratio: {:.6}", self.type_token_ratio)?;
+        writeln!(f, "hapax_legomena: {}", self.hapax_legomena)?;
+        writeln!(f, "dis_legomena: {}", self.dis_legomena)?;
+        writeln!(f, "yules_k: {:.6}", self.yules_k)?;
+        writeln!(f, "simpsons_d: {:.6}", self.simpsons_d)?;
+        match self.zipf_exponent {
+            Some(s) => write!(f, "zipf_exponent: {:.6}", s),
+            None => write!(f, "zipf_exponent: -"),
+        }
+    }
+}
+
+/// 順位と出現頻度の対数に直線を当てはめ、その傾きの符号を反転した値を返す。
+fn zipf_exponent(freqs: &Frequencies) -> Option<f64> {
+    if freqs.distinct() < 2 {
+        return None;
+    }
+    let points: Vec<(f64, f64)> = freqs
+        .sorted_by_count()
+        .iter()
+        .enumerate()
+        .map(|(i, &(_, count))| (((i + 1) as f64).ln(), (count as f64).ln()))
+        .collect();
+    let len = points.len() as f64;
+    let mean_x = points.iter().map(|p| p.0).sum::<f64>() / len;
+    let mean_y = points.iter().map(|p| p.1).sum::<f64>() / len;
+    let (mut covariance, mut variance) = (0.0, 0.0);
+    for (x, y) in points {
+        covariance += (x - mean_x) * (y - mean_y);
+        variance += (x - mean_x) * (x - mean_x);
+    }
+    // 全てのトークンの出現頻度が同じ場合に、`-0.0`を返さないようにする。
+    Some(if covariance == 0.0 {
+        0.0
+    } else {
+        -covariance / variance
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn freqs(counts: &[usize]) -> Frequencies {
+        counts
+            .iter()
+            .enumerate()
+            .map(|(i, &c)| (i.to_string(), c))
+            .collect()
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn known_values() {
+        let stats = Statistics::new(&freqs(&[3, 2, 1, 1, 1]));
+        assert_eq!((stats.tokens, stats.types), (8, 5));
+        assert_eq!((stats.hapax_legomena, stats.dis_legomena), (3, 1));
+        assert_close(stats.type_token_ratio, 5.0 / 8.0);
+        // Σf² = 16
+        assert_close(stats.yules_k, 1e4 * (16.0 - 8.0) / 64.0);
+        // Σf(f-1) = 6 + 2
+        assert_close(stats.simpsons_d, 8.0 / 56.0);
+        let expected_entropy = -[3.0, 2.0, 1.0, 1.0, 1.0]
+            .iter()
+            .map(|f: &f64| f / 8.0 * (f / 8.0).log2())
+            .sum::<f64>();
+        assert_close(stats.entropy, expected_entropy);
+    }
+
+    #[test]
+    fn zipf_exponent_of_exact_power_law() {
+        let counts: Vec<usize> = (1..=10).map(|r| 2520 / r).collect();
+        assert_close(Statistics::new(&freqs(&counts)).zipf_exponent.unwrap(), 1.0);
+
+        let counts: Vec<usize> = (1..=6).map(|r| 3600 / (r * r)).collect();
+        assert_close(Statistics::new(&freqs(&counts)).zipf_exponent.unwrap(), 2.0);
+    }
+
+    #[test]
+    fn degenerate_inputs() {
+        let empty = Statistics::new(&Frequencies::new());
+        assert_eq!(empty.tokens, 0);
+        assert_eq!(empty.entropy, 0.0);
+        assert_eq!(empty.type_token_ratio, 0.0);
+        assert_eq!(empty.yules_k, 0.0);
+        assert_eq!(empty.zipf_exponent, None);
+
+        let single = Statistics::new(&freqs(&[1]));
+        assert_eq!(single.entropy, 0.0);
+        assert_eq!(single.simpsons_d, 0.0);
+        assert_eq!(single.zipf_exponent, None);
+    }
+}
@@ -0,0 +1,448 @@
+//! 英単語を語幹に変換するステミングの機能を提供する。
+//!
+//! 語幹はPorterのステミングアルゴリズムで求める。ただし、`ran`や`went`のような不規則変化は
+//! Porterのアルゴリズムでは扱えないため、よく使われる不規則変化は原形に戻してから語幹を求める。
+use std::collections::HashMap;
+
+/// 不規則変化した語形と、その原形（単語の昇順）
+const IRREGULAR_FORMS: &[(&str, &str)] = &[
+    ("am", "be"),
+    ("are", "be"),
+    ("ate", "eat"),
+    ("became", "become"),
+    ("been", "be"),
+    ("began", "begin"),
+    ("begun", "begin"),
+    ("bought", "buy"),
+    ("broke", "break"),
+    ("broken", "break"),
+    ("brought", "bring"),
+    ("built", "build"),
+    ("came", "come"),
+    ("caught", "catch"),
+    ("children", "child"),
+    ("chose", "choose"),
+    ("chosen", "choose"),
+    ("did", "do"),
+    ("does", "do"),
+    ("done", "do"),
+    ("drawn", "draw"),
+    ("drew", "draw"),
+    ("driven", "drive"),
+    ("drove", "drive"),
+    ("eaten", "eat"),
+    ("feet", "foot"),
+    ("flew", "fly"),
+    ("flown", "fly"),
+    ("fought", "fight"),
+    ("gave", "give"),
+    ("given", "give"),
+    ("gone", "go"),
+    ("got", "get"),
+    ("gotten", "get"),
+    ("grew", "grow"),
+    ("grown", "grow"),
+    ("had", "have"),
+    ("has", "have"),
+    ("heard", "hear"),
+    ("held", "hold"),
+    ("is", "be"),
+    ("kept", "keep"),
+    ("knew", "know"),
+    ("known", "know"),
+    ("made", "make"),
+    ("men", "man"),
+    ("mice", "mouse"),
+    ("paid", "pay"),
+    ("people", "person"),
+    ("ran", "run"),
+    ("said", "say"),
+    ("sang", "sing"),
+    ("seen", "see"),
+    ("sent", "send"),
+    ("spent", "spend"),
+    ("spoke", "speak"),
+    ("spoken", "speak"),
+    ("stood", "stand"),
+    ("sung", "sing"),
+    ("swam", "swim"),
+    ("swum", "swim"),
+    ("taken", "take"),
+    ("taught", "teach"),
+    ("teeth", "tooth"),
+    ("thought", "think"),
+    ("threw", "throw"),
+    ("thrown", "throw"),
+    ("told", "tell"),
+    ("took", "take"),
+    ("understood", "understand"),
+    ("was", "be"),
+    ("went", "go"),
+    ("were", "be"),
+    ("women", "woman"),
+    ("written", "write"),
+    ("wrote", "write"),
+];
+
+/// `word`の語幹を返す。
+///
+/// ASCIIの英字だけからなる単語は、小文字に変換してから語幹を求める。
+/// それ以外の単語は、そのまま返す。
+///
+/// ```
+/// use kuroyasu_bicycle_book_wordcount::stem::stem;
+///
+/// assert_eq!(stem("running"), "run");
+/// assert_eq!(stem("Runs"), "run");
+/// assert_eq!(stem("ran"), "run");
+/// assert_eq!(stem("relational"), "relat");
+/// assert_eq!(stem("東京"), "東京");
+/// ```
+pub fn stem(word: &str) -> String {
+    let mut buf = String::new();
+    stem_into(word, &mut buf);
+    buf
+}
+
+/// `word`の語幹を`buf`に書き込む。`buf`の内容は上書きされる。
+///
+/// [`stem`](fn.stem.html)と同じ語幹を求めるが、`buf`の領域を再利用する。
+pub fn stem_into(word: &str, buf: &mut String) {
+    buf.clear();
+    if word.is_empty() || !word.bytes().all(|b| b.is_ascii_alphabetic()) {
+        buf.push_str(word);
+        return;
+    }
+
+    let mut b = std::mem::take(buf).into_bytes();
+    b.extend(word.bytes().map(|b| b.to_ascii_lowercase()));
+    // 小文字に変換した後の単語で不規則変化を検索する。
+    let irregular = IRREGULAR_FORMS.binary_search_by(|&(form, _)| form.as_bytes().cmp(&b));
+    if let Ok(i) = irregular {
+        b.clear();
+        b.extend_from_slice(IRREGULAR_FORMS[i].1.as_bytes());
+    }
+    if b.len() > 2 {
+        let mut stemmer = Porter { b, j: 0 };
+        stemmer.step1ab();
+        stemmer.step1c();
+        stemmer.step2();
+        stemmer.step3();
+        stemmer.step4();
+        stemmer.step5();
+        b = stemmer.b;
+    }
+    // ASCIIの英字だけを操作しているため、UTF-8として正しい。
+    *buf = String::from_utf8(b).unwrap();
+}
+
+/// Porterのステミングアルゴリズムの状態
+///
+/// `b`は処理中の単語で、`j`は[`ends`](#method.ends)が一致した接尾辞を除いた語幹の終端を表す。
+struct Porter {
+    b: Vec<u8>,
+    j: isize,
+}
+
+impl Porter {
+    fn k(&self) -> isize {
+        self.b.len() as isize - 1
+    }
+
+    /// `i`番目の文字が子音であれば`true`を返す。
+    fn cons(&self, i: isize) -> bool {
+        match self.b[i as usize] {
+            b'a' | b'e' | b'i' | b'o' | b'u' => false,
+            b'y' => i == 0 || !self.cons(i - 1),
+            _ => true,
+        }
+    }
+
+    /// `j`番目までの語幹に含まれる、母音と子音の並びの数を返す。
+    fn m(&self) -> usize {
+        let mut n = 0;
+        let mut i = 0;
+        loop {
+            if i > self.j {
+                return n;
+            }
+            if !self.cons(i) {
+                break;
+            }
+            i += 1;
+        }
+        i += 1;
+        loop {
+            loop {
+                if i > self.j {
+                    return n;
+                }
+                if self.cons(i) {
+                    break;
+                }
+                i += 1;
+            }
+            i += 1;
+            n += 1;
+            loop {
+                if i > self.j {
+                    return n;
+                }
+                if !self.cons(i) {
+                    break;
+                }
+                i += 1;
+            }
+            i += 1;
+        }
+    }
+
+    /// `j`番目までの語幹に母音が含まれていれば`true`を返す。
+    fn vowel_in_stem(&self) -> bool {
+        (0..=self.j).any(|i| !self.cons(i))
+    }
+
+    /// `i-1`番目と`i`番目の文字が同じ子音であれば`true`を返す。
+    fn double_cons(&self, i: isize) -> bool {
+        i >= 1 && self.b[i as usize] == self.b[i as usize - 1] && self.cons(i)
+    }
+
+    /// `i-2`番目から`i`番目の文字が子音、母音、子音の順で並び、最後の子音が`w`、`x`及び`y`でなければ`true`を返す。
+    fn cvc(&self, i: isize) -> bool {
+        if i < 2 || !self.cons(i) || self.cons(i - 1) || !self.cons(i - 2) {
+            return false;
+        }
+        !matches!(self.b[i as usize], b'w' | b'x' | b'y')
+    }
+
+    /// 単語が`suffix`で終わっていれば、`j`を接尾辞の直前に設定して`true`を返す。
+    fn ends(&mut self, suffix: &str) -> bool {
+        if !self.b.ends_with(suffix.as_bytes()) {
+            return false;
+        }
+        self.j = self.k() - suffix.len() as isize;
+        true
+    }
+
+    /// `j`番目より後ろを`s`に置き換える。
+    fn set_to(&mut self, s: &str) {
+        self.b.truncate((self.j + 1) as usize);
+        self.b.extend_from_slice(s.as_bytes());
+    }
+
+    /// 語幹の`m`が`0`より大きければ、`j`番目より後ろを`s`に置き換える。
+    fn replace(&mut self, s: &str) {
+        if self.m() > 0 {
+            self.set_to(s);
+        }
+    }
+
+    /// `suffixes`のうち最初に一致した接尾辞を、対応する文字列に置き換える。
+    fn replace_suffix(&mut self, suffixes: &[(&str, &str)]) {
+        if let Some(&(_, s)) = suffixes.iter().find(|(suffix, _)| self.ends(suffix)) {
+            self.replace(s);
+        }
+    }
+
+    /// 複数形と、`-ed`及び`-ing`を取り除く。
+    fn step1ab(&mut self) {
+        if self.b.ends_with(b"s") {
+            if self.ends("sses") || self.ends("ies") {
+                self.b.truncate(self.b.len() - 2);
+            } else if self.b[self.b.len() - 2] != b's' {
+                self.b.pop();
+            }
+        }
+        if self.ends("eed") {
+            if self.m() > 0 {
+                self.b.pop();
+            }
+        } else if (self.ends("ed") || self.ends("ing")) && self.vowel_in_stem() {
+            self.b.truncate((self.j + 1) as usize);
+            if self.ends("at") {
+                self.set_to("ate");
+            } else if self.ends("bl") {
+                self.set_to("ble");
+            } else if self.ends("iz") {
+                self.set_to("ize");
+            } else if self.double_cons(self.k()) {
+                if !matches!(self.b[self.b.len() - 1], b'l' | b's' | b'z') {
+                    self.b.pop();
+                }
+            } else {
+                self.j = self.k();
+                if self.m() == 1 && self.cvc(self.k()) {
+                    self.b.push(b'e');
+                }
+            }
+        }
+    }
+
+    /// 語幹に母音を含む場合に、末尾の`y`を`i`に置き換える。
+    fn step1c(&mut self) {
+        if self.ends("y") && self.vowel_in_stem() {
+            let k = self.k() as usize;
+            self.b[k] = b'i';
+        }
+    }
+
+    /// 二重の接尾辞を1つの接尾辞に置き換える。
+    fn step2(&mut self) {
+        self.replace_suffix(&[
+            ("ational", "ate"),
+            ("tional", "tion"),
+            ("enci", "ence"),
+            ("anci", "ance"),
+            ("izer", "ize"),
+            ("bli", "ble"),
+            ("alli", "al"),
+            ("entli", "ent"),
+            ("eli", "e"),
+            ("ousli", "ous"),
+            ("ization", "ize"),
+            ("ation", "ate"),
+            ("ator", "ate"),
+            ("alism", "al"),
+            ("iveness", "ive"),
+            ("fulness", "ful"),
+            ("ousness", "ous"),
+            ("aliti", "al"),
+            ("iviti", "ive"),
+            ("biliti", "ble"),
+            ("logi", "log"),
+        ]);
+    }
+
+    /// `-ic`、`-full`及び`-ness`などを取り除く。
+    fn step3(&mut self) {
+        self.replace_suffix(&[
+            ("icate", "ic"),
+            ("ative", ""),
+            ("alize", "al"),
+            ("iciti", "ic"),
+            ("ical", "ic"),
+            ("ful", ""),
+            ("ness", ""),
+        ]);
+    }
+
+    /// `m`が`1`より大きい語幹から、`-ant`及び`-ence`などを取り除く。
+    fn step4(&mut self) {
+        const SUFFIXES: &[&str] = &[
+            "al", "ance", "ence", "er", "ic", "able", "ible", "ant", "ement", "ment", "ent", "ion",
+            "ou", "ism", "ate", "iti", "ous", "ive", "ize",
+        ];
+        let Some(&suffix) = SUFFIXES.iter().find(|suffix| self.ends(suffix)) else {
+            return;
+        };
+        if suffix == "ion" && (self.j < 0 || !matches!(self.b[self.j as usize], b's' | b't')) {
+            return;
+        }
+        if self.m() > 1 {
+            self.b.truncate((self.j + 1) as usize);
+        }
+    }
+
+    /// 末尾の`-e`と、`m`が`1`より大きい語幹の末尾の`-ll`を整理する。
+    fn step5(&mut self) {
+        self.j = self.k();
+        if self.b.ends_with(b"e") {
+            let m = self.m();
+            if m > 1 || m == 1 && !self.cvc(self.k() - 1) {
+                self.b.pop();
+            }
+        }
+        if self.b.ends_with(b"l") && self.double_cons(self.k()) && self.m() > 1 {
+            self.b.pop();
+        }
+    }
+}
+
+/// 語幹ごとの出現頻度と、その語幹に変換された語形ごとの出現頻度
+///
+/// [`count_stems`](../fn.count_stems.html)で数える。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StemCount {
+    count: usize,
+    surfaces: HashMap<String, usize>,
+}
+
+impl StemCount {
+    pub(crate) fn add(&mut self, surface: &str) {
+        self.count += 1;
+        crate::increment(&mut self.surfaces, surface);
+    }
+
+    /// 語幹の出現頻度を返す。
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// 語幹に変換された語形ごとの出現頻度を返す。
+    pub fn surfaces(&self) -> &HashMap<String, usize> {
+        &self.surfaces
+    }
+
+    /// 最も出現頻度が高い語形を返す。出現頻度が同じ語形は、辞書順で最初の語形を返す。
+    pub fn most_frequent_surface(&self) -> &str {
+        self.surfaces
+            .iter()
+            .max_by(|a, b| a.1.cmp(b.1).then_with(|| b.0.cmp(a.0)))
+            .map_or("", |(surface, _)| surface)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn porter_examples() {
+        // Porterの論文に掲載されている例
+        let examples = [
+            ("caresses", "caress"),
+            ("ponies", "poni"),
+            ("caress", "caress"),
+            ("cats", "cat"),
+            ("feed", "feed"),
+            ("agreed", "agre"),
+            ("plastered", "plaster"),
+            ("motoring", "motor"),
+            ("sing", "sing"),
+            ("conflated", "conflat"),
+            ("troubled", "troubl"),
+            ("sized", "size"),
+            ("hopping", "hop"),
+            ("falling", "fall"),
+            ("failing", "fail"),
+            ("filing", "file"),
+            ("happy", "happi"),
+            ("conditional", "condit"),
+            ("generalization", "gener"),
+            ("hopefulness", "hope"),
+            ("adjustment", "adjust"),
+            ("adoption", "adopt"),
+            ("controlling", "control"),
+            ("rate", "rate"),
+            ("cease", "ceas"),
+        ];
+        for (word, expected) in examples {
+            assert_eq!(stem(word), expected, "{}", word);
+        }
+    }
+
+    #[test]
+    fn irregular_forms_are_sorted() {
+        assert!(IRREGULAR_FORMS.windows(2).all(|w| w[0].0 < w[1].0));
+    }
+
+    #[test]
+    fn most_frequent_surface() {
+        let mut count = StemCount::default();
+        ["runs", "running", "ran", "running", "runs"]
+            .iter()
+            .for_each(|s| count.add(s));
+        assert_eq!(count.count(), 5);
+        assert_eq!(count.most_frequent_surface(), "running");
+    }
+}
@@ -0,0 +1,131 @@
+//! 出現頻度を数えないストップワードを扱う機能を提供する。
+//!
+//! 英語と日本語のストップワードが同梱されているほか、ファイルから読み込むこともできる。
+//! ストップワードの比較は完全一致で行うため、大文字と小文字を区別せずに除外したい場合は、
+//! [`normalize`](../normalize/index.html)で正規化した後に除外すること。
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+/// 同梱する英語のストップワード
+const ENGLISH: &str = include_str!("en.txt");
+/// 同梱する日本語のストップワード
+const JAPANESE: &str = include_str!("ja.txt");
+
+/// ストップワードの集合
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Stopwords {
+    words: HashSet<String>,
+}
+
+impl Stopwords {
+    /// 空の集合を返す。
+    pub fn new() -> Stopwords {
+        Stopwords::default()
+    }
+
+    /// 同梱の英語のストップワードを返す。
+    pub fn english() -> Stopwords {
+        Stopwords::parse(ENGLISH)
+    }
+
+    /// 同梱の日本語のストップワードを返す。
+    pub fn japanese() -> Stopwords {
+        Stopwords::parse(JAPANESE)
+    }
+
+    /// `en`または`ja`の言語コードから、同梱のストップワードを返す。
+    ///
+    /// 言語コードが不明な場合は`None`を返す。
+    pub fn for_language(code: &str) -> Option<Stopwords> {
+        match code {
+            "en" => Some(Stopwords::english()),
+            "ja" => Some(Stopwords::japanese()),
+            _ => None,
+        }
+    }
+
+    /// 1行に1語ずつ記録されたストップワードを読み込む。
+    ///
+    /// 前後の空白は取り除き、空行と`#`で始まる行は無視する。
+    ///
+    /// # Errors
+    ///
+    /// 読み込みに失敗した場合は、エラーを返す。
+    pub fn from_reader(reader: impl BufRead) -> io::Result<Stopwords> {
+        let mut stopwords = Stopwords::new();
+        for line in reader.lines() {
+            stopwords.insert_line(&line?);
+        }
+        Ok(stopwords)
+    }
+
+    /// ファイルからストップワードを読み込む。形式は[`from_reader`](#method.from_reader)と同じ。
+    ///
+    /// # Errors
+    ///
+    /// ファイルの読み込みに失敗した場合は、エラーを返す。
+    pub fn from_file(path: impl AsRef<Path>) -> io::Result<Stopwords> {
+        Stopwords::from_reader(BufReader::new(File::open(path)?))
+    }
+
+    fn parse(list: &str) -> Stopwords {
+        let mut stopwords = Stopwords::new();
+        list.lines().for_each(|line| stopwords.insert_line(line));
+        stopwords
+    }
+
+    fn insert_line(&mut self, line: &str) {
+        let word = line.trim();
+        if !word.is_empty() && !word.starts_with('#') {
+            self.insert(word);
+        }
+    }
+
+    /// ストップワードを追加する。
+    pub fn insert(&mut self, word: impl Into<String>) {
+        self.words.insert(word.into());
+    }
+
+    /// `other`のストップワードを全て追加する。
+    pub fn extend(&mut self, other: Stopwords) {
+        self.words.extend(other.words);
+    }
+
+    /// `word`がストップワードであれば`true`を返す。
+    pub fn contains(&self, word: &str) -> bool {
+        self.words.contains(word)
+    }
+
+    /// ストップワードの数を返す。
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    /// ストップワードがなければ`true`を返す。
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_lists() {
+        assert!(Stopwords::english().contains("the"));
+        assert!(!Stopwords::english().contains("#"));
+        assert!(Stopwords::japanese().contains("の"));
+        assert!(Stopwords::for_language("fr").is_none());
+    }
+
+    #[test]
+    fn read_from_reader() {
+        let stopwords = Stopwords::from_reader("# comment\n foo \n\nbar\n".as_bytes()).unwrap();
+        assert_eq!(stopwords.len(), 2);
+        assert!(stopwords.contains("foo"));
+        assert!(stopwords.contains("bar"));
+    }
+}
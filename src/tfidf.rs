@@ -0,0 +1,249 @@
+//! 複数の文書の出現頻度から、文書ごとに特徴的なトークンをTF-IDFやBM25で求める機能を提供する。
+//!
+//! 文書ごとの出現頻度は、[`count`](../fn.count.html)などで数えたものを
+//! [`Corpus::add`](struct.Corpus.html#method.add)で追加する。
+use std::collections::HashMap;
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use crate::Frequencies;
+
+/// [`Corpus`](struct.Corpus.html)に追加した1つの文書
+#[derive(Debug, Clone)]
+pub struct Document {
+    name: String,
+    freqs: Frequencies,
+}
+
+impl Document {
+    /// 文書の名前を返す。
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// 文書のトークンの出現頻度を返す。
+    pub fn frequencies(&self) -> &Frequencies {
+        &self.freqs
+    }
+}
+
+/// BM25のパラメーター
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bm25 {
+    /// 出現頻度による重みの飽和の度合い
+    pub k1: f64,
+    /// 文書の長さによる正規化の度合い（`0.0`から`1.0`）
+    pub b: f64,
+}
+
+/// `k1`は`1.2`、`b`は`0.75`とする。
+impl Default for Bm25 {
+    fn default() -> Self {
+        Bm25 { k1: 1.2, b: 0.75 }
+    }
+}
+
+/// 文書に出現したトークンと、その重み
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct TermWeight<'a> {
+    /// トークン
+    pub term: &'a str,
+    /// 文書での出現頻度
+    pub count: usize,
+    /// TF-IDFまたはBM25の重み
+    pub weight: f64,
+}
+
+/// 文書の集まりと、各トークンが出現した文書の数（文書頻度）
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use kuroyasu_bicycle_book_wordcount::tfidf::Corpus;
+/// use kuroyasu_bicycle_book_wordcount::{count, CountOption, Frequencies};
+///
+/// let mut corpus = Corpus::new();
+/// for (name, text) in [("a.txt", "the cat sat"), ("b.txt", "the dog ran"), ("c.txt", "the cat ran")] {
+///     corpus.add(name, Frequencies::from(count(Cursor::new(text), CountOption::Word)));
+/// }
+/// assert_eq!(corpus.document_frequency("the"), 3);
+/// assert_eq!(corpus.document_frequency("cat"), 2);
+///
+/// let weights = corpus.tf_idf(0);
+/// assert_eq!(weights[0].term, "sat");
+/// assert_eq!(weights.last().unwrap().weight, 0.0); // 全ての文書に出現する`the`
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Corpus {
+    documents: Vec<Document>,
+    document_frequency: HashMap<String, usize>,
+    /// 全ての文書のトークンの延べ数の合計
+    total_tokens: usize,
+}
+
+impl Corpus {
+    /// 文書のない集まりを作成する。
+    pub fn new() -> Self {
+        Corpus::default()
+    }
+
+    /// `name`という名前で、出現頻度が`freqs`の文書を追加する。
+    pub fn add(&mut self, name: impl Into<String>, freqs: Frequencies) {
+        for (term, _) in &freqs {
+            crate::increment(&mut self.document_frequency, term);
+        }
+        self.total_tokens += freqs.total();
+        self.documents.push(Document {
+            name: name.into(),
+            freqs,
+        });
+    }
+
+    /// 追加した順に、文書を返す。
+    pub fn documents(&self) -> &[Document] {
+        &self.documents
+    }
+
+    /// 文書の数を返す。
+    pub fn len(&self) -> usize {
+        self.documents.len()
+    }
+
+    /// 文書がなければ`true`を返す。
+    pub fn is_empty(&self) -> bool {
+        self.documents.is_empty()
+    }
+
+    /// `term`が出現した文書の数を返す。
+    pub fn document_frequency(&self, term: &str) -> usize {
+        self.document_frequency.get(term).copied().unwrap_or(0)
+    }
+
+    /// `term`の逆文書頻度`ln(N / df)`を返す。`N`は文書の数、`df`は文書頻度。
+    ///
+    /// どの文書にも出現しないトークンは`0.0`を返す。
+    pub fn idf(&self, term: &str) -> f64 {
+        match self.document_frequency(term) {
+            0 => 0.0,
+            df => (self.len() as f64 / df as f64).ln(),
+        }
+    }
+
+    /// `index`番目の文書の全てのトークンを、TF-IDFの重みが大きい順に返す。
+    ///
+    /// TFは文書での相対頻度、IDFは[`idf`](#method.idf)とする。このため、全ての文書に出現する
+    /// トークンの重みは`0.0`になる。重みが同じトークンは辞書順に並べる。
+    ///
+    /// # Panics
+    ///
+    /// `index`が文書の数以上の場合は、パニックを起こす。
+    pub fn tf_idf(&self, index: usize) -> Vec<TermWeight<'_>> {
+        let freqs = &self.documents[index].freqs;
+        let total = freqs.total() as f64;
+        self.weights(freqs, |term, count| count as f64 / total * self.idf(term))
+    }
+
+    /// `index`番目の文書の全てのトークンを、`params`で求めたBM25の重みが大きい順に返す。
+    ///
+    /// IDFは`ln(1 + (N - df + 0.5) / (df + 0.5))`とし、全ての文書に出現するトークンでも
+    /// 負にならない。重みが同じトークンは辞書順に並べる。
+    ///
+    /// # Panics
+    ///
+    /// `index`が文書の数以上の場合は、パニックを起こす。
+    pub fn bm25(&self, index: usize, params: Bm25) -> Vec<TermWeight<'_>> {
+        let freqs = &self.documents[index].freqs;
+        let n = self.len() as f64;
+        let average_length = self.total_tokens as f64 / n;
+        let length_norm = if average_length > 0.0 {
+            1.0 - params.b + params.b * freqs.total() as f64 / average_length
+        } else {
+            1.0
+        };
+        self.weights(freqs, |term, count| {
+            let df = self.document_frequency(term) as f64;
+            let idf = (1.0 + (n - df + 0.5) / (df + 0.5)).ln();
+            let tf = count as f64;
+            idf * tf * (params.k1 + 1.0) / (tf + params.k1 * length_norm)
+        })
+    }
+
+    /// `freqs`の全てのトークンの重みを`weight`で求め、重みが大きい順に返す。
+    fn weights<'a>(
+        &self,
+        freqs: &'a Frequencies,
+        weight: impl Fn(&str, usize) -> f64,
+    ) -> Vec<TermWeight<'a>> {
+        let mut weights: Vec<_> = freqs
+            .iter()
+            .map(|(term, count)| TermWeight {
+                term,
+                count,
+                weight: weight(term, count),
+            })
+            .collect();
+        weights.sort_by(|a, b| {
+            b.weight
+                .total_cmp(&a.weight)
+                .then_with(|| a.term.cmp(b.term))
+        });
+        weights
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn corpus(documents: &[&[(&str, usize)]]) -> Corpus {
+        let mut corpus = Corpus::new();
+        for (i, pairs) in documents.iter().enumerate() {
+            let freqs = pairs.iter().map(|&(k, c)| (k.to_string(), c)).collect();
+            corpus.add(format!("doc{}", i), freqs);
+        }
+        corpus
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn tf_idf_values() {
+        let corpus = corpus(&[&[("a", 3), ("b", 1)], &[("a", 1), ("c", 1)]]);
+        let weights = corpus.tf_idf(0);
+        assert_eq!(weights[0].term, "b");
+        assert_close(weights[0].weight, 0.25 * 2f64.ln());
+        assert_eq!((weights[1].term, weights[1].count), ("a", 3));
+        assert_eq!(weights[1].weight, 0.0);
+    }
+
+    #[test]
+    fn bm25_values() {
+        let corpus = corpus(&[&[("a", 2), ("b", 2)], &[("a", 1), ("c", 1)]]);
+        let params = Bm25::default();
+        let weights = corpus.bm25(0, params);
+        // 平均の長さは3で、文書0の長さは4
+        let norm = 1.0 - 0.75 + 0.75 * 4.0 / 3.0;
+        let idf = |df: f64| (1.0 + (2.0 - df + 0.5) / (df + 0.5)).ln();
+        let expected = |df: f64| idf(df) * 2.0 * 2.2 / (2.0 + 1.2 * norm);
+        assert_eq!(weights[0].term, "b");
+        assert_close(weights[0].weight, expected(1.0));
+        assert_close(weights[1].weight, expected(2.0));
+        assert!(weights[1].weight > 0.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn out_of_range() {
+        Corpus::new().tf_idf(0);
+    }
+}
@@ -0,0 +1,539 @@
+//! 入力の各行から、出現頻度を数えるトークンを取り出す[`Tokenizer`](trait.Tokenizer.html)と、
+//! その組み込みの実装を提供する。
+//!
+//! [`CountOption`](../enum.CountOption.html)の各モードは、このモジュールのトークナイザーで
+//! 実装されている。独自のトークナイザーを実装すれば、[`count_with`](../fn.count_with.html)で
+//! 組み込みのモードと同じように出現頻度を数えられる。
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+use crate::grapheme;
+use crate::morph::{Dictionary, MorphemeOption};
+use crate::ngram::Window;
+use crate::normalize::Normalizer;
+use crate::pattern::Pattern;
+use crate::stem;
+use crate::stopwords::Stopwords;
+
+/// 入力の各行からトークンを取り出すトークナイザー
+///
+/// 行をまたいで状態を保持するトークナイザーは、入力の終わりで呼ばれる
+/// [`finish`](#method.finish)で、残りのトークンを渡すことができる。
+///
+/// # Examples
+///
+/// `ABC-1234`の形式の製品コードを数えるトークナイザーの例。
+///
+/// ```
+/// use std::io::Cursor;
+/// use kuroyasu_bicycle_book_wordcount::count_with;
+/// use kuroyasu_bicycle_book_wordcount::tokenizer::Tokenizer;
+///
+/// struct ProductCode;
+///
+/// impl Tokenizer for ProductCode {
+///     fn tokenize(&mut self, line: &str, emit: &mut dyn FnMut(&str)) {
+///         for word in line.split(|c: char| !c.is_ascii_alphanumeric() && c != '-') {
+///             let is_code = word.len() == 8
+///                 && word[..3].bytes().all(|b| b.is_ascii_uppercase())
+///                 && word[3..4] == *"-"
+///                 && word[4..].bytes().all(|b| b.is_ascii_digit());
+///             if is_code {
+///                 emit(word);
+///             }
+///         }
+///     }
+/// }
+///
+/// let input = Cursor::new("ABC-1234 was replaced by XYZ-0001.\nABC-1234 is discontinued.");
+/// let freqs = count_with(input, ProductCode);
+/// assert_eq!(freqs["ABC-1234"], 2);
+/// assert_eq!(freqs["XYZ-0001"], 1);
+/// ```
+pub trait Tokenizer {
+    /// 改行を取り除いた1行からトークンを取り出し、順に`emit`に渡す。
+    fn tokenize(&mut self, line: &str, emit: &mut dyn FnMut(&str));
+
+    /// 入力の終わりで呼ばれ、保持しているトークンがあれば`emit`に渡す。
+    ///
+    /// 既定の実装は何もしない。
+    fn finish(&mut self, emit: &mut dyn FnMut(&str)) {
+        let _ = emit;
+    }
+}
+
+impl<T: Tokenizer + ?Sized> Tokenizer for &mut T {
+    fn tokenize(&mut self, line: &str, emit: &mut dyn FnMut(&str)) {
+        (**self).tokenize(line, emit)
+    }
+
+    fn finish(&mut self, emit: &mut dyn FnMut(&str)) {
+        (**self).finish(emit)
+    }
+}
+
+impl<T: Tokenizer + ?Sized> Tokenizer for Box<T> {
+    fn tokenize(&mut self, line: &str, emit: &mut dyn FnMut(&str)) {
+        (**self).tokenize(line, emit)
+    }
+
+    fn finish(&mut self, emit: &mut dyn FnMut(&str)) {
+        (**self).finish(emit)
+    }
+}
+
+/// 行のUTF-8のバイトごとに、小文字の16進数の文字列をトークンとして取り出すトークナイザー
+///
+/// 行末の改行は含まない。
+#[derive(Debug, Clone, Default)]
+pub struct ByteTokenizer;
+
+impl Tokenizer for ByteTokenizer {
+    fn tokenize(&mut self, line: &str, emit: &mut dyn FnMut(&str)) {
+        const HEX: &[u8; 16] = b"0123456789abcdef";
+        for b in line.bytes() {
+            let hex = [HEX[usize::from(b >> 4)], HEX[usize::from(b & 0xf)]];
+            // 16進数の数字はASCII文字であるため、UTF-8として正しい。
+            emit(std::str::from_utf8(&hex).unwrap());
+        }
+    }
+}
+
+/// Unicodeの1文字ごとにトークンを取り出すトークナイザー
+#[derive(Debug, Clone, Default)]
+pub struct CharTokenizer;
+
+impl Tokenizer for CharTokenizer {
+    fn tokenize(&mut self, line: &str, emit: &mut dyn FnMut(&str)) {
+        for (i, c) in line.char_indices() {
+            emit(&line[i..i + c.len_utf8()]);
+        }
+    }
+}
+
+/// 拡張書記素クラスタごとにトークンを取り出すトークナイザー
+#[derive(Debug, Clone, Default)]
+pub struct GraphemeTokenizer;
+
+impl Tokenizer for GraphemeTokenizer {
+    fn tokenize(&mut self, line: &str, emit: &mut dyn FnMut(&str)) {
+        grapheme::graphemes(line).for_each(emit);
+    }
+}
+
+/// 正規表現`\w+`にマッチする単語ごとにトークンを取り出すトークナイザー
+#[derive(Debug, Clone, Default)]
+pub struct WordTokenizer;
+
+impl Tokenizer for WordTokenizer {
+    fn tokenize(&mut self, line: &str, emit: &mut dyn FnMut(&str)) {
+        // 正規表現は最初に使用するときに一度だけコンパイルし、全てのスレッドで共有する。
+        static WORD: OnceLock<Regex> = OnceLock::new();
+        let word = WORD.get_or_init(|| Regex::new(r"\w+").expect("word pattern is valid"));
+        for m in word.find_iter(line) {
+            emit(m.as_str());
+        }
+    }
+}
+
+/// [`LineTokenizer`](struct.LineTokenizer.html)で使用する、行を整えるオプション
+///
+/// 既定では、行をそのまま数える。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct LineOption {
+    /// 行の先頭と末尾の空白を取り除く。
+    pub trim: bool,
+    /// 連続する空白を、1つの半角スペースに置き換える。
+    pub collapse_whitespace: bool,
+    /// 空白だけの行を数えない。
+    pub skip_blank: bool,
+    /// 大文字と小文字を区別せずに数え、最初に出現した行をキーとする。
+    pub ignore_case: bool,
+}
+
+/// 行全体を1つのトークンとするトークナイザー
+#[derive(Debug, Clone, Default)]
+pub struct LineTokenizer {
+    option: LineOption,
+    buf: String,
+    folded: String,
+    /// 大文字と小文字を区別しない場合の、ケースフォールディングした行から最初に出現した行への対応
+    first_seen: HashMap<String, String>,
+}
+
+impl LineTokenizer {
+    /// `option`に従って整えた行を取り出すトークナイザーを作成する。
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use kuroyasu_bicycle_book_wordcount::tokenizer::{LineOption, LineTokenizer};
+    /// use kuroyasu_bicycle_book_wordcount::count_with;
+    ///
+    /// let option = LineOption {
+    ///     trim: true,
+    ///     ignore_case: true,
+    ///     ..Default::default()
+    /// };
+    /// let input = Cursor::new("Foo\n  foo \nFOO\n");
+    /// let freqs = count_with(input, LineTokenizer::new(option));
+    /// assert_eq!(freqs["Foo"], 3);
+    /// ```
+    pub fn new(option: LineOption) -> Self {
+        LineTokenizer {
+            option,
+            ..Default::default()
+        }
+    }
+}
+
+impl Tokenizer for LineTokenizer {
+    fn tokenize(&mut self, line: &str, emit: &mut dyn FnMut(&str)) {
+        let LineTokenizer {
+            option,
+            buf,
+            folded,
+            first_seen,
+        } = self;
+        if option.skip_blank && line.trim().is_empty() {
+            return;
+        }
+        let mut line = line;
+        if option.trim {
+            line = line.trim();
+        }
+        if option.collapse_whitespace {
+            buf.clear();
+            let mut in_whitespace = false;
+            for c in line.chars() {
+                if !c.is_whitespace() {
+                    buf.push(c);
+                } else if !in_whitespace {
+                    buf.push(' ');
+                }
+                in_whitespace = c.is_whitespace();
+            }
+            line = buf.as_str();
+        }
+        if !option.ignore_case {
+            emit(line);
+            return;
+        }
+        let normalizer = Normalizer {
+            case_fold: true,
+            ..Default::default()
+        };
+        normalizer.normalize_into(line, folded);
+        match first_seen.get(folded.as_str()) {
+            Some(first) => emit(first),
+            None => emit(
+                first_seen
+                    .entry(folded.clone())
+                    .or_insert_with(|| line.to_string()),
+            ),
+        }
+    }
+}
+
+/// 形態素解析した形態素ごとにトークンを取り出すトークナイザー
+#[derive(Debug, Clone)]
+pub struct MorphemeTokenizer<'d> {
+    dict: &'d Dictionary,
+    option: MorphemeOption,
+    key: String,
+}
+
+impl<'d> MorphemeTokenizer<'d> {
+    /// 辞書`dict`を使用するトークナイザーを作成する。
+    pub fn new(dict: &'d Dictionary, option: MorphemeOption) -> Self {
+        MorphemeTokenizer {
+            dict,
+            option,
+            key: String::new(),
+        }
+    }
+}
+
+impl Tokenizer for MorphemeTokenizer<'_> {
+    fn tokenize(&mut self, line: &str, emit: &mut dyn FnMut(&str)) {
+        for m in self.dict.tokenize(line) {
+            if self.option.key(&m, &mut self.key) {
+                emit(&self.key);
+            }
+        }
+    }
+}
+
+/// 正規表現のパターンにマッチした部分ごとにトークンを取り出す。
+impl Tokenizer for Pattern {
+    fn tokenize(&mut self, line: &str, emit: &mut dyn FnMut(&str)) {
+        self.for_each_match(line, emit);
+    }
+}
+
+/// 別のトークナイザーが取り出した連続する`n`個のトークンを連結して、n-gramを取り出すトークナイザー
+#[derive(Debug, Clone)]
+pub struct NgramTokenizer<T> {
+    inner: T,
+    window: Window,
+    cross_lines: bool,
+    key: String,
+}
+
+impl<T: Tokenizer> NgramTokenizer<T> {
+    /// `inner`のトークンを`separator`で連結したn-gramを取り出すトークナイザーを作成する。
+    ///
+    /// `cross_lines`が`true`の場合、行をまたぐn-gramも取り出す。`n`が`0`の場合は何も取り出さない。
+    pub fn new(inner: T, n: usize, separator: impl Into<String>, cross_lines: bool) -> Self {
+        NgramTokenizer {
+            inner,
+            window: Window::new(n, separator),
+            cross_lines,
+            key: String::new(),
+        }
+    }
+}
+
+impl<T: Tokenizer> Tokenizer for NgramTokenizer<T> {
+    fn tokenize(&mut self, line: &str, emit: &mut dyn FnMut(&str)) {
+        let NgramTokenizer {
+            inner,
+            window,
+            cross_lines,
+            key,
+        } = self;
+        if !*cross_lines {
+            window.clear();
+        }
+        inner.tokenize(line, &mut |token| {
+            if window.push(token, key) {
+                emit(key);
+            }
+        });
+    }
+
+    fn finish(&mut self, emit: &mut dyn FnMut(&str)) {
+        let NgramTokenizer {
+            inner, window, key, ..
+        } = self;
+        inner.finish(&mut |token| {
+            if window.push(token, key) {
+                emit(key);
+            }
+        });
+    }
+}
+
+/// 別のトークナイザーが取り出したトークンを正規化するトークナイザー
+///
+/// ```
+/// use std::io::Cursor;
+/// use kuroyasu_bicycle_book_wordcount::normalize::{Normalizer, UnicodeForm};
+/// use kuroyasu_bicycle_book_wordcount::tokenizer::{NormalizedTokenizer, WordTokenizer};
+/// use kuroyasu_bicycle_book_wordcount::count_with;
+///
+/// let normalizer = Normalizer {
+///     case_fold: true,
+///     form: Some(UnicodeForm::Nfkc),
+///     ..Default::default()
+/// };
+/// let tokenizer = NormalizedTokenizer::new(WordTokenizer, normalizer);
+/// let freqs = count_with(Cursor::new("Rust rust ＲＵＳＴ"), tokenizer);
+/// assert_eq!(freqs["rust"], 3);
+/// ```
+#[derive(Debug, Clone)]
+pub struct NormalizedTokenizer<T> {
+    inner: T,
+    normalizer: Normalizer,
+    buf: String,
+}
+
+impl<T: Tokenizer> NormalizedTokenizer<T> {
+    /// `inner`のトークンを`normalizer`で正規化するトークナイザーを作成する。
+    pub fn new(inner: T, normalizer: Normalizer) -> Self {
+        NormalizedTokenizer {
+            inner,
+            normalizer,
+            buf: String::new(),
+        }
+    }
+}
+
+impl<T: Tokenizer> Tokenizer for NormalizedTokenizer<T> {
+    fn tokenize(&mut self, line: &str, emit: &mut dyn FnMut(&str)) {
+        let NormalizedTokenizer {
+            inner,
+            normalizer,
+            buf,
+        } = self;
+        inner.tokenize(line, &mut |token| {
+            normalizer.normalize_into(token, buf);
+            emit(buf);
+        });
+    }
+
+    fn finish(&mut self, emit: &mut dyn FnMut(&str)) {
+        let NormalizedTokenizer {
+            inner,
+            normalizer,
+            buf,
+        } = self;
+        inner.finish(&mut |token| {
+            normalizer.normalize_into(token, buf);
+            emit(buf);
+        });
+    }
+}
+
+/// 別のトークナイザーが取り出したトークンのうち、ストップワードを取り除くトークナイザー
+///
+/// 取り除いたトークンは出現頻度を数えるマップに渡らないため、エントリーも作成されない。
+///
+/// ```
+/// use std::io::Cursor;
+/// use kuroyasu_bicycle_book_wordcount::stopwords::Stopwords;
+/// use kuroyasu_bicycle_book_wordcount::tokenizer::{StopwordTokenizer, WordTokenizer};
+/// use kuroyasu_bicycle_book_wordcount::count_with;
+///
+/// let tokenizer = StopwordTokenizer::new(WordTokenizer, Stopwords::english());
+/// let freqs = count_with(Cursor::new("the art of the deal"), tokenizer);
+/// assert_eq!(freqs.len(), 2);
+/// assert_eq!(freqs["art"], 1);
+/// ```
+#[derive(Debug, Clone)]
+pub struct StopwordTokenizer<T> {
+    inner: T,
+    stopwords: Stopwords,
+}
+
+impl<T: Tokenizer> StopwordTokenizer<T> {
+    /// `inner`のトークンから`stopwords`を取り除くトークナイザーを作成する。
+    pub fn new(inner: T, stopwords: Stopwords) -> Self {
+        StopwordTokenizer { inner, stopwords }
+    }
+}
+
+impl<T: Tokenizer> Tokenizer for StopwordTokenizer<T> {
+    fn tokenize(&mut self, line: &str, emit: &mut dyn FnMut(&str)) {
+        let StopwordTokenizer { inner, stopwords } = self;
+        inner.tokenize(line, &mut |token| {
+            if !stopwords.contains(token) {
+                emit(token);
+            }
+        });
+    }
+
+    fn finish(&mut self, emit: &mut dyn FnMut(&str)) {
+        let StopwordTokenizer { inner, stopwords } = self;
+        inner.finish(&mut |token| {
+            if !stopwords.contains(token) {
+                emit(token);
+            }
+        });
+    }
+}
+
+/// 別のトークナイザーが取り出した英単語を、語幹に変換するトークナイザー
+///
+/// 語幹は[`stem::stem`](../stem/fn.stem.html)で求める。
+///
+/// ```
+/// use std::io::Cursor;
+/// use kuroyasu_bicycle_book_wordcount::tokenizer::{StemTokenizer, WordTokenizer};
+/// use kuroyasu_bicycle_book_wordcount::count_with;
+///
+/// let tokenizer = StemTokenizer::new(WordTokenizer);
+/// let freqs = count_with(Cursor::new("running runs ran"), tokenizer);
+/// assert_eq!(freqs["run"], 3);
+/// ```
+#[derive(Debug, Clone)]
+pub struct StemTokenizer<T> {
+    inner: T,
+    buf: String,
+}
+
+impl<T: Tokenizer> StemTokenizer<T> {
+    /// `inner`のトークンを語幹に変換するトークナイザーを作成する。
+    pub fn new(inner: T) -> Self {
+        StemTokenizer {
+            inner,
+            buf: String::new(),
+        }
+    }
+}
+
+impl<T: Tokenizer> Tokenizer for StemTokenizer<T> {
+    fn tokenize(&mut self, line: &str, emit: &mut dyn FnMut(&str)) {
+        let StemTokenizer { inner, buf } = self;
+        inner.tokenize(line, &mut |token| {
+            stem::stem_into(token, buf);
+            emit(buf);
+        });
+    }
+
+    fn finish(&mut self, emit: &mut dyn FnMut(&str)) {
+        let StemTokenizer { inner, buf } = self;
+        inner.finish(&mut |token| {
+            stem::stem_into(token, buf);
+            emit(buf);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(mut tokenizer: impl Tokenizer, lines: &[&str]) -> Vec<String> {
+        let mut tokens = Vec::new();
+        let mut emit = |token: &str| tokens.push(token.to_string());
+        for line in lines {
+            tokenizer.tokenize(line, &mut emit);
+        }
+        tokenizer.finish(&mut emit);
+        tokens
+    }
+
+    #[test]
+    fn builtin_tokenizers() {
+        assert_eq!(tokens(CharTokenizer, &["aあ"]), ["a", "あ"]);
+        assert_eq!(tokens(WordTokenizer, &["aa, bb"]), ["aa", "bb"]);
+        assert_eq!(
+            tokens(LineTokenizer::default(), &["aa, bb", ""]),
+            ["aa, bb", ""]
+        );
+        assert_eq!(tokens(ByteTokenizer, &["aあ"]), ["61", "e3", "81", "82"]);
+    }
+
+    #[test]
+    fn word_tokenizer_matches_regex() {
+        let re = regex::Regex::new(r"\w+").unwrap();
+        for line in [
+            "snake_case, Ünïcödé-wörds",
+            "東京都に住む。x1",
+            "ｶﾀｶﾅ\u{301}a٣b\u{200d}c",
+            "",
+        ] {
+            let expected: Vec<_> = re.find_iter(line).map(|m| m.as_str()).collect();
+            assert_eq!(tokens(WordTokenizer, &[line]), expected, "{}", line);
+        }
+    }
+
+    #[test]
+    fn ngram_of_custom_tokenizer() {
+        let words = NgramTokenizer::new(WordTokenizer, 2, "+", true);
+        assert_eq!(tokens(words, &["a b", "c"]), ["a+b", "b+c"]);
+    }
+
+    #[test]
+    fn stopwords_after_normalization() {
+        let normalizer = Normalizer {
+            case_fold: true,
+            ..Default::default()
+        };
+        let words = NormalizedTokenizer::new(WordTokenizer, normalizer);
+        let words = StopwordTokenizer::new(words, Stopwords::english());
+        assert_eq!(tokens(words, &["The Rust Book"]), ["rust", "book"]);
+    }
+}
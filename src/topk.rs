@@ -0,0 +1,275 @@
+//! 使用するメモリーの量を制限して、出現頻度が高いトークンを近似的に数える機能を提供する。
+//!
+//! ほとんどのトークンが一度しか出現しない入力では、全てのトークンを数えると
+//! `HashMap`が際限なく大きくなる。[`TopK`](struct.TopK.html)はSpace-Savingアルゴリズムで、
+//! 決められたメモリーの量に収まる数のトークンだけを数える。
+use std::collections::HashMap;
+use std::mem;
+use std::sync::Arc;
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+/// 1つのトークンを数えるために使用するメモリーのうち、トークンの長さによらない部分の概算
+///
+/// `Vec`と`HashMap`の容量の余裕を見込んで、要素の大きさの2倍とする。
+const ENTRY_OVERHEAD: usize = 2 * (mem::size_of::<Entry>() + mem::size_of::<(Arc<str>, usize)>())
+    + 2 * mem::size_of::<usize>();
+
+#[derive(Debug, Clone)]
+struct Entry {
+    key: Arc<str>,
+    count: usize,
+    error: usize,
+}
+
+/// Space-Savingアルゴリズムで、出現頻度が高いトークンを近似的に数えるカウンター
+///
+/// 数えているトークンが使用するメモリーの量が`memory`バイトを超える場合は、出現頻度が最も低い
+/// トークンを追い出して、その出現頻度を新しいトークンの出現頻度に引き継ぐ。このため、
+/// 出現頻度は実際より多く数えることがあるが、その誤差の上限は[`HeavyHitter::error`]で分かる。
+/// 全体の出現回数の`1/m`より多く出現したトークンは、必ず数えられている。ただし、`m`は
+/// 追い出しが発生したときに数えていたトークンの数。
+///
+/// [`HeavyHitter::error`]: struct.HeavyHitter.html#structfield.error
+///
+/// # Examples
+///
+/// ```
+/// use kuroyasu_bicycle_book_wordcount::topk::TopK;
+///
+/// let mut top = TopK::new(2, 1024);
+/// for key in "a b a c a b d e a".split(' ') {
+///     top.add(key);
+/// }
+/// let hitters = top.top();
+/// assert_eq!(hitters[0].key, "a");
+/// assert_eq!(hitters[0].count, 4);
+/// assert_eq!(top.total(), 9);
+/// ```
+#[derive(Debug, Clone)]
+pub struct TopK {
+    k: usize,
+    memory: usize,
+    used: usize,
+    total: usize,
+    /// これまでに追い出したトークンの出現頻度の最大値。数えていないトークンの実際の出現頻度は、
+    /// この値以下である。
+    floor: usize,
+    /// 出現頻度が最も低いトークンを先頭とする二分ヒープ
+    heap: Vec<Entry>,
+    /// トークンから`heap`の位置への対応
+    positions: HashMap<Arc<str>, usize>,
+}
+
+/// [`TopK`](struct.TopK.html)が数えたトークンと、その出現頻度
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct HeavyHitter<'a> {
+    /// トークン
+    pub key: &'a str,
+    /// 数えた出現頻度。実際の出現頻度は、`count - error`以上`count`以下である。
+    pub count: usize,
+    /// 数えた出現頻度の誤差の上限
+    pub error: usize,
+}
+
+impl HeavyHitter<'_> {
+    /// 実際の出現頻度の下限を返す。
+    pub fn guaranteed(&self) -> usize {
+        self.count - self.error
+    }
+}
+
+impl TopK {
+    /// 出現頻度が高い`k`個のトークンを、おおむね`memory`バイトのメモリーで数えるカウンターを作成する。
+    ///
+    /// `memory`は、トークンを数えるために使用するメモリーの概算の上限である。ただし、1つのトークンが
+    /// `memory`を超える場合でも、そのトークンは数える。
+    pub fn new(k: usize, memory: usize) -> Self {
+        TopK {
+            k,
+            memory,
+            used: 0,
+            total: 0,
+            floor: 0,
+            heap: Vec::new(),
+            positions: HashMap::new(),
+        }
+    }
+
+    /// `key`の出現を数える。
+    pub fn add(&mut self, key: &str) {
+        self.total += 1;
+        if let Some(&i) = self.positions.get(key) {
+            self.heap[i].count += 1;
+            self.sift_down(i);
+            return;
+        }
+
+        // メモリーの上限を超える場合は、出現頻度が最も低いトークンから追い出す。追い出しが
+        // 発生しなくても、以前に追い出したトークンかもしれないため、出現頻度は`floor`から数える。
+        let size = key.len() + ENTRY_OVERHEAD;
+        while self.used + size > self.memory && !self.heap.is_empty() {
+            let evicted = self.pop_min();
+            self.floor = self.floor.max(evicted);
+        }
+        let key: Arc<str> = Arc::from(key);
+        self.used += size;
+        self.positions.insert(key.clone(), self.heap.len());
+        self.heap.push(Entry {
+            key,
+            count: self.floor + 1,
+            error: self.floor,
+        });
+        self.sift_up(self.heap.len() - 1);
+    }
+
+    /// 数えた出現頻度が高い順に、最大`k`個のトークンを返す。出現頻度が同じトークンは辞書順に並べる。
+    pub fn top(&self) -> Vec<HeavyHitter<'_>> {
+        let mut hitters: Vec<_> = self
+            .heap
+            .iter()
+            .map(|e| HeavyHitter {
+                key: &e.key,
+                count: e.count,
+                error: e.error,
+            })
+            .collect();
+        hitters.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.key.cmp(b.key)));
+        hitters.truncate(self.k);
+        hitters
+    }
+
+    /// `key`を数えていれば、数えた出現頻度と誤差の上限を返す。
+    pub fn get(&self, key: &str) -> Option<HeavyHitter<'_>> {
+        self.positions.get(key).map(|&i| {
+            let e = &self.heap[i];
+            HeavyHitter {
+                key: &e.key,
+                count: e.count,
+                error: e.error,
+            }
+        })
+    }
+
+    /// これまでに数えた全てのトークンの出現回数を返す。
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// 現在数えているトークンの数を返す。
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    /// 数えているトークンがなければ`true`を返す。
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    /// 出現頻度が最も低いトークンを取り除き、その出現頻度を返す。
+    fn pop_min(&mut self) -> usize {
+        let last = self.heap.len() - 1;
+        self.swap(0, last);
+        let entry = self.heap.pop().unwrap();
+        self.positions.remove(&entry.key);
+        self.used -= entry.key.len() + ENTRY_OVERHEAD;
+        if !self.heap.is_empty() {
+            self.sift_down(0);
+        }
+        entry.count
+    }
+
+    fn sift_up(&mut self, mut i: usize) {
+        while i > 0 {
+            let parent = (i - 1) / 2;
+            if self.heap[parent].count <= self.heap[i].count {
+                break;
+            }
+            self.swap(i, parent);
+            i = parent;
+        }
+    }
+
+    fn sift_down(&mut self, mut i: usize) {
+        loop {
+            let mut min = i;
+            for child in [2 * i + 1, 2 * i + 2] {
+                if child < self.heap.len() && self.heap[child].count < self.heap[min].count {
+                    min = child;
+                }
+            }
+            if min == i {
+                break;
+            }
+            self.swap(i, min);
+            i = min;
+        }
+    }
+
+    fn swap(&mut self, i: usize, j: usize) {
+        self.heap.swap(i, j);
+        *self.positions.get_mut(&self.heap[i].key).unwrap() = i;
+        *self.positions.get_mut(&self.heap[j].key).unwrap() = j;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exact_within_memory() {
+        let mut top = TopK::new(10, 1 << 20);
+        for key in ["a", "b", "a", "c", "a", "b"] {
+            top.add(key);
+        }
+        let hitters = top.top();
+        assert_eq!(hitters.len(), 3);
+        assert!(hitters.iter().all(|h| h.error == 0));
+        assert_eq!(top.get("b").unwrap().count, 2);
+    }
+
+    #[test]
+    fn error_bounds_hold_when_evicting() {
+        // 3個のトークンしか数えられないメモリーで、10種類の数字に混ざった頻出トークンを数える。
+        let mut top = TopK::new(1, 3 * (1 + ENTRY_OVERHEAD));
+        let mut expected = HashMap::new();
+        for i in 0..1000 {
+            let key = if i % 3 == 0 {
+                "x".to_string()
+            } else {
+                (i % 10).to_string()
+            };
+            top.add(&key);
+            *expected.entry(key).or_insert(0) += 1;
+            assert!(top.len() <= 3);
+        }
+        for (key, count) in expected {
+            if let Some(h) = top.get(&key) {
+                assert!(h.guaranteed() <= count && count <= h.count, "{}", key);
+            }
+        }
+        assert_eq!(top.top()[0].key, "x");
+    }
+
+    #[test]
+    fn error_bounds_hold_after_evicting_several() {
+        // 長いトークンが複数のトークンを追い出して空いたメモリーに、以前に追い出したトークンが
+        // 追い出しなしで戻る場合も、誤差の上限を引き継ぐ。
+        let mut top = TopK::new(10, 3 * (1 + ENTRY_OVERHEAD));
+        let long = "x".repeat(ENTRY_OVERHEAD + 2);
+        top.add("a");
+        for _ in 0..5 {
+            top.add("b");
+            top.add("c");
+        }
+        top.add(&long);
+        top.add("d");
+        top.add("e");
+        top.add("a");
+        let a = top.get("a").unwrap();
+        assert!(a.guaranteed() <= 2 && 2 <= a.count, "{:?}", a);
+    }
+}
@@ -0,0 +1,40 @@
+use std::io::Cursor;
+
+use kuroyasu_bicycle_book_wordcount::{count, count_bytes, CountOption};
+
+#[macro_use]
+mod utils;
+
+#[test]
+fn bytecount_works() {
+    // UTF-8として正しくないバイト列も数えられる。
+    let input = Cursor::new(b"\xff\xfe\r\n\xff");
+    let freqs = count(input, CountOption::Byte);
+    assert_eq!(freqs.len(), 4);
+    assert_map!(freqs, {
+        "ff" => 2,
+        "fe" => 1,
+        "0d" => 1,
+        "0a" => 1
+    });
+}
+
+#[test]
+fn byte_ngram_works() {
+    let input = Cursor::new(b"\xca\xfe\xba\xbe\xca\xfe");
+    let freqs = count(input, CountOption::ByteNgram { n: 2 });
+    assert_map!(freqs, {
+        "cafe" => 2,
+        "feba" => 1,
+        "babe" => 1,
+        "beca" => 1
+    });
+}
+
+#[test]
+fn byte_histogram() {
+    let histogram = count_bytes(&b"aab\n"[..]);
+    assert_eq!(histogram.iter().sum::<usize>(), 4);
+    assert_eq!(histogram[usize::from(b'a')], 2);
+    assert_eq!(histogram[usize::from(b'\n')], 1);
+}
@@ -0,0 +1,20 @@
+use std::io::Cursor;
+
+use kuroyasu_bicycle_book_wordcount::{count, count_distinct, CountOption};
+
+#[test]
+fn distinct_lines_across_files() {
+    let first: String = (0..3000).map(|i| format!("line {}\n", i)).collect();
+    let second: String = (2000..6000).map(|i| format!("line {}\n", i)).collect();
+
+    let mut hll = count_distinct(Cursor::new(&first), CountOption::Line.tokenizer(), 14);
+    hll.merge(&count_distinct(
+        Cursor::new(&second),
+        CountOption::Line.tokenizer(),
+        14,
+    ));
+
+    let exact = count(Cursor::new(first + &second), CountOption::Line).len() as f64;
+    assert_eq!(exact, 6000.0);
+    assert!((hll.estimate() - exact).abs() / exact < 3.0 * hll.standard_error());
+}
@@ -0,0 +1,168 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+/// テストごとに異なる一時ファイルに`contents`を書き込む。
+fn temp_file(name: &str, contents: &[u8]) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("wordcount-cli-{}-{}", std::process::id(), name));
+    fs::write(&path, contents).unwrap();
+    path
+}
+
+/// `args`を引数としてコマンドを実行する。
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_kuroyasu-bicycle-book-wordcount"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+#[test]
+fn ignore_case_lines_with_jobs() {
+    let contents = "Tokyo\n".repeat(2000) + &"TOKYO\n".repeat(2000);
+    let path = temp_file("ignore-case", contents.as_bytes());
+    let path = path.to_str().unwrap();
+    for jobs in ["1", "4"] {
+        let output = run(&[
+            "--mode",
+            "line",
+            "--ignore-case",
+            "--jobs",
+            jobs,
+            "--format",
+            "tsv",
+            path,
+        ]);
+        assert!(output.status.success());
+        assert_eq!(
+            stdout(&output),
+            "key\tcount\nTokyo\t4000\n",
+            "jobs = {}",
+            jobs
+        );
+    }
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn pattern_ngrams() {
+    let path = temp_file("pattern-ngram", b"E1 x E2 y E1\nE2 E1\n");
+    let path = path.to_str().unwrap();
+    let output = run(&["--pattern", r"E\d", "--ngram", "2", "--format", "tsv", path]);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "key\tcount\nE2 E1\t2\nE1 E2\t1\n");
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn missing_file() {
+    let output = run(&["wordcount-missing-file.txt"]);
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.starts_with("wordcount-missing-file.txt: "),
+        "{}",
+        stderr
+    );
+    assert!(!stderr.contains("panicked"));
+}
+
+#[test]
+fn skip_missing_file_in_batch() {
+    let path = temp_file("batch", b"alpha beta\n");
+    let path = path.to_str().unwrap();
+    let output = run(&[
+        "tfidf",
+        "--format",
+        "tsv",
+        "wordcount-missing-file.txt",
+        path,
+    ]);
+    assert_eq!(output.status.code(), Some(1));
+    let rows = stdout(&output);
+    assert!(rows.starts_with("document\tterm\tcount\tweight\n"));
+    assert_eq!(rows.lines().count(), 3);
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("wordcount-missing-file.txt"));
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn morpheme_base_form_and_pos() {
+    let path = temp_file("morpheme", "東京都に住んでいる\n京都に住む\n".as_bytes());
+    let path = path.to_str().unwrap();
+    let args = [
+        "--mode",
+        "morpheme",
+        "--base-form",
+        "--pos",
+        "verb",
+        "--format",
+        "tsv",
+        path,
+    ];
+    let output = run(&args);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "key\tcount\n住む\t2\nいる\t1\n");
+
+    let args = [
+        "--mode",
+        "morpheme",
+        "--base-form",
+        "--with-pos",
+        "--pos",
+        "verb",
+        "--format",
+        "tsv",
+        path,
+    ];
+    let output = run(&args);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "key\tcount\n住む/動詞\t2\nいる/動詞\t1\n");
+
+    for option in ["--base-form", "--with-pos"] {
+        let output = run(&[option, path]);
+        assert_eq!(output.status.code(), Some(2));
+    }
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn morpheme_ipadic_dictionary() {
+    // 同梱の辞書の連接コスト表と未知語の定義に、「東京都」だけを収録した辞書
+    let bundled = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("src/morph/dict");
+    let dir = std::env::temp_dir().join(format!("wordcount-cli-{}-dict", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    for name in ["matrix.def", "unk.def"] {
+        fs::copy(bundled.join(name), dir.join(name)).unwrap();
+    }
+    let entry = "東京都,1,1,100,名詞,固有名詞,地域,一般,*,*,東京都,トウキョウト,トーキョート\n";
+    fs::write(dir.join("lexicon.csv"), entry).unwrap();
+    let path = temp_file("ipadic", "東京都\n".as_bytes());
+    let path = path.to_str().unwrap();
+
+    let output = run(&["--mode", "morpheme", "--format", "tsv", path]);
+    assert_ne!(stdout(&output), "key\tcount\n東京都\t1\n");
+    let dict = dir.to_str().unwrap();
+    let output = run(&[
+        "--mode", "morpheme", "--dict", dict, "--format", "tsv", path,
+    ]);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "key\tcount\n東京都\t1\n");
+
+    let output = run(&[
+        "--mode",
+        "morpheme",
+        "--dict",
+        "wordcount-missing-dict",
+        path,
+    ]);
+    assert_eq!(output.status.code(), Some(2));
+    fs::remove_file(path).unwrap();
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,55 @@
+use std::io::Cursor;
+
+use kuroyasu_bicycle_book_wordcount::collocation::Association;
+use kuroyasu_bicycle_book_wordcount::tokenizer::WordTokenizer;
+use kuroyasu_bicycle_book_wordcount::{count_collocations, CountOption};
+
+#[test]
+fn fixed_phrases_rank_first() {
+    let text = "\
+machine learning is popular
+deep learning is a kind of machine learning
+machine learning needs data
+the data is large
+出力 結果 を 確認 する
+出力 結果 が 正しい
+";
+    let collocations = count_collocations(Cursor::new(text), WordTokenizer);
+    assert_eq!(collocations.count("machine", "learning"), 3);
+    assert_eq!(collocations.count("出力", "結果"), 2);
+    // 行をまたぐ`popular`と`deep`の組は数えない。
+    assert_eq!(collocations.count("popular", "deep"), 0);
+
+    for association in [
+        Association::Pmi,
+        Association::TScore,
+        Association::LogLikelihood,
+    ] {
+        let ranked = collocations.rank(association, 2);
+        assert!(ranked.iter().all(|c| c.count >= 2));
+        let pairs: Vec<_> = ranked.iter().map(|c| (c.first, c.second)).collect();
+        assert!(
+            pairs.contains(&("machine", "learning")),
+            "{:?}",
+            association
+        );
+        assert!(pairs.contains(&("出力", "結果")), "{:?}", association);
+        assert!(ranked
+            .windows(2)
+            .all(|w| w[0].score(association) >= w[1].score(association)));
+    }
+    assert_eq!(collocations.rank(Association::Pmi, 2)[0].first, "出力");
+    assert_eq!(
+        collocations.rank(Association::TScore, 2)[0].first,
+        "machine"
+    );
+}
+
+#[test]
+fn character_pairs() {
+    let collocations = count_collocations(Cursor::new("abab\nba"), CountOption::Char.tokenizer());
+    assert_eq!(collocations.count("a", "b"), 2);
+    assert_eq!(collocations.count("b", "a"), 2);
+    assert_eq!(collocations.total(), 4);
+    assert!(collocations.rank(Association::Pmi, 3).is_empty());
+}
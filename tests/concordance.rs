@@ -0,0 +1,76 @@
+use std::io::Cursor;
+
+use kuroyasu_bicycle_book_wordcount::concordance::{find, find_by, Context, Occurrence};
+use kuroyasu_bicycle_book_wordcount::normalize::Normalizer;
+use kuroyasu_bicycle_book_wordcount::tokenizer::{NormalizedTokenizer, WordTokenizer};
+use kuroyasu_bicycle_book_wordcount::CountOption;
+
+#[test]
+fn word_context() {
+    let text = "one two key three four\n\nkey at start\nends with key";
+    let occurrences = find(Cursor::new(text), WordTokenizer, "key", Context::Tokens(1));
+    assert_eq!(
+        occurrences,
+        [
+            Occurrence {
+                line: 1,
+                offset: 8,
+                left: "two ".to_string(),
+                keyword: "key".to_string(),
+                right: " three".to_string(),
+            },
+            Occurrence {
+                line: 3,
+                offset: 0,
+                left: "".to_string(),
+                keyword: "key".to_string(),
+                right: " at".to_string(),
+            },
+            Occurrence {
+                line: 4,
+                offset: 10,
+                left: "with ".to_string(),
+                keyword: "key".to_string(),
+                right: "".to_string(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn char_context_in_japanese() {
+    let text = "機械学習の出力結果を確認する";
+    let occurrences = find(
+        Cursor::new(text),
+        CountOption::Char.tokenizer(),
+        "出",
+        Context::Chars(2),
+    );
+    assert_eq!(occurrences.len(), 1);
+    assert_eq!(occurrences[0].left, "習の");
+    assert_eq!(occurrences[0].right, "力結");
+    assert_eq!(occurrences[0].offset, "機械学習の".len());
+}
+
+#[test]
+fn normalized_tokens() {
+    let text = "Rust, RUST and rust";
+    let normalizer = Normalizer {
+        case_fold: true,
+        ..Normalizer::default()
+    };
+    // 正規化したトークンは行中にないため、大文字のトークンは見つからない。
+    let tokenizer = NormalizedTokenizer::new(WordTokenizer, normalizer);
+    let occurrences = find(Cursor::new(text), tokenizer, "rust", Context::Tokens(0));
+    assert_eq!(occurrences.len(), 1);
+    assert_eq!(occurrences[0].offset, 15);
+
+    let occurrences = find_by(
+        Cursor::new(text),
+        WordTokenizer,
+        |token| normalizer.normalize(token) == "rust",
+        Context::Tokens(0),
+    );
+    let keywords: Vec<_> = occurrences.iter().map(|o| o.keyword.as_str()).collect();
+    assert_eq!(keywords, ["Rust", "RUST", "rust"]);
+}
@@ -0,0 +1,72 @@
+use std::io::Cursor;
+
+use kuroyasu_bicycle_book_wordcount::{count, CountOption, Counter};
+
+#[macro_use]
+mod utils;
+
+#[test]
+fn counter_handles_lines_split_across_chunks() {
+    let mut counter = Counter::new(CountOption::Line);
+    for chunk in ["aa\r", "\nb", "b\n\naa", "\r\n", "bb"] {
+        counter.feed(chunk);
+    }
+    assert_map!(counter.frequencies(), {
+        "aa" => 2,
+        "bb" => 1,
+        "" => 1
+    });
+    let freqs = counter.finish();
+    assert_map!(freqs, {
+        "aa" => 2,
+        "bb" => 2,
+        "" => 1
+    });
+}
+
+#[test]
+fn counter_matches_count() {
+    let text = "東京都に住んでいる\n京都に住む\n";
+    let option = CountOption::CharNgram {
+        n: 2,
+        cross_lines: true,
+    };
+    let mut counter = Counter::new(option);
+    for c in text.chars() {
+        counter.feed(c.encode_utf8(&mut [0; 4]));
+    }
+    assert_eq!(counter.finish(), count(Cursor::new(text), option));
+}
+
+#[test]
+fn counter_keeps_trailing_cr() {
+    let text = "aa\r\nbb\r";
+    let mut counter = Counter::new(CountOption::Line);
+    counter.feed(text);
+    let freqs = counter.finish();
+    assert_map!(freqs, {
+        "aa" => 1,
+        "bb\r" => 1
+    });
+    assert_eq!(freqs, count(Cursor::new(text), CountOption::Line));
+}
+
+#[test]
+fn merged_counters() {
+    let mut counter = Counter::default();
+    counter.feed("aa bb\n");
+    let mut other = Counter::default();
+    other.feed("bb cc");
+    counter.merge(other);
+    assert_map!(counter.frequencies(), {
+        "aa" => 1,
+        "bb" => 2,
+        "cc" => 1
+    });
+}
+
+#[test]
+#[should_panic]
+fn counter_rejects_bytes() {
+    Counter::new(CountOption::Byte);
+}
@@ -0,0 +1,31 @@
+use std::collections::HashMap;
+use std::io::Cursor;
+
+use kuroyasu_bicycle_book_wordcount::{count, CountOption, Frequencies};
+
+#[test]
+fn frequencies_from_count() {
+    let freqs = Frequencies::from(count(Cursor::new("b a c\nb a\nb d d"), CountOption::Word));
+    assert_eq!(freqs.total(), 8);
+    assert_eq!(freqs.distinct(), 4);
+    assert_eq!(freqs.most_common(3), [("b", 3), ("a", 2), ("d", 2)]);
+    assert_eq!(freqs.relative("a"), 0.25);
+    assert_eq!(freqs.relative("z"), 0.0);
+    assert_eq!(freqs.iter().map(|(_, c)| c).sum::<usize>(), 8);
+
+    let frequent = freqs.clone().with_min_count(2);
+    assert_eq!(frequent.sorted_by_count(), [("b", 3), ("a", 2), ("d", 2)]);
+    assert_eq!(frequent.total(), 7);
+
+    let map: HashMap<String, usize> = freqs.into();
+    assert_eq!(map["c"], 1);
+}
+
+#[test]
+fn empty_frequencies() {
+    let freqs = Frequencies::new();
+    assert!(freqs.is_empty());
+    assert_eq!(freqs.total(), 0);
+    assert_eq!(freqs.relative("a"), 0.0);
+    assert!(freqs.most_common(5).is_empty());
+}
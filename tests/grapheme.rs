@@ -0,0 +1,29 @@
+use std::io::Cursor;
+
+use kuroyasu_bicycle_book_wordcount::{count, CountOption};
+
+#[macro_use]
+mod utils;
+
+#[test]
+fn graphemecount_combining_marks() {
+    // 「が」を、結合済みの文字と「か」+結合用濁点の2通りで記録する。
+    let input = Cursor::new("が\u{304b}\u{3099}\u{304b}\u{3099}");
+    let freqs = count(input, CountOption::Grapheme);
+    assert_eq!(freqs.len(), 2);
+    assert_map!(freqs, {
+        "が" => 1,
+        "\u{304b}\u{3099}" => 2
+    });
+}
+
+#[test]
+fn graphemecount_emoji() {
+    let input = Cursor::new("🇯🇵👨‍👩‍👧🇯🇵\n👨‍👩‍👧");
+    let freqs = count(input, CountOption::Grapheme);
+    assert_eq!(freqs.len(), 2);
+    assert_map!(freqs, {
+        "🇯🇵" => 2,
+        "👨‍👩‍👧" => 2
+    });
+}
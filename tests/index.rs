@@ -0,0 +1,115 @@
+use std::io::Cursor;
+
+use kuroyasu_bicycle_book_wordcount::index::{InvertedIndex, Position};
+use kuroyasu_bicycle_book_wordcount::tokenizer::WordTokenizer;
+use kuroyasu_bicycle_book_wordcount::CountOption;
+
+#[test]
+fn positions_in_files() {
+    let mut index = InvertedIndex::new();
+    index
+        .add("a.txt", Cursor::new("key one\n\nkey key"), WordTokenizer)
+        .unwrap();
+    index
+        .add(
+            "b.txt",
+            Cursor::new("日本語 の key"),
+            CountOption::Word.tokenizer(),
+        )
+        .unwrap();
+    assert_eq!(index.files(), ["a.txt", "b.txt"]);
+    let position = |file, line, offset, column| Position {
+        file,
+        line,
+        offset,
+        column,
+    };
+    assert_eq!(
+        index.positions("key"),
+        [
+            position(0, 1, 0, 1),
+            position(0, 3, 0, 1),
+            position(0, 3, 4, 5),
+            position(1, 1, "日本語 の ".len(), 7),
+        ]
+    );
+    assert_eq!(index.positions("missing"), []);
+    assert_eq!(index.frequencies().total(), 7);
+}
+
+#[test]
+fn normalized_keys() {
+    let mut index = InvertedIndex::new();
+    index
+        .add_by(
+            "a.txt",
+            Cursor::new("The cat and THE dog"),
+            WordTokenizer,
+            |token| {
+                let token = token.to_lowercase();
+                (token != "and").then_some(token)
+            },
+        )
+        .unwrap();
+    assert_eq!(index.len(), 3);
+    let offsets: Vec<_> = index.positions("the").iter().map(|p| p.offset).collect();
+    assert_eq!(offsets, [0, 12]);
+}
+
+#[test]
+fn round_trip() {
+    let mut index = InvertedIndex::new();
+    index
+        .add("dir\\name\twith tab", Cursor::new("a b\na"), WordTokenizer)
+        .unwrap();
+    index
+        .add_by("b.txt", Cursor::new("x"), WordTokenizer, |_| {
+            Some("odd\tkey\n".to_string())
+        })
+        .unwrap();
+
+    let mut buffer = Vec::new();
+    index.write_to(&mut buffer).unwrap();
+    let text = String::from_utf8(buffer.clone()).unwrap();
+    assert_eq!(
+        text,
+        "# wordcount index 1\n\
+         F\tdir\\\\name\\twith tab\n\
+         F\tb.txt\n\
+         K\ta\t0:1:0:1\t0:2:0:1\n\
+         K\tb\t0:1:2:3\n\
+         K\todd\\tkey\\n\t1:1:0:1\n"
+    );
+    assert_eq!(
+        InvertedIndex::from_reader(Cursor::new(buffer)).unwrap(),
+        index
+    );
+}
+
+#[test]
+fn invalid_data() {
+    for text in [
+        "",
+        "F\ta.txt\n",
+        "# wordcount index 1\nX\ta\n",
+        "# wordcount index 1\nK\ta\t0:1:0:1\n",
+        "# wordcount index 1\nF\ta\nK\ta\t0:1:0\n",
+    ] {
+        let error = InvertedIndex::from_reader(Cursor::new(text)).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData, "{:?}", text);
+    }
+}
+
+#[test]
+fn failed_file_is_not_recorded() {
+    let mut index = InvertedIndex::new();
+    index
+        .add("a.txt", Cursor::new("key one"), WordTokenizer)
+        .unwrap();
+    let input = Cursor::new(b"key two\n\xff\n".to_vec());
+    assert!(index.add("bad.txt", input, WordTokenizer).is_err());
+    assert_eq!(index.files(), ["a.txt"]);
+    assert_eq!(index.positions("key").len(), 1);
+    assert!(index.positions("two").is_empty());
+    assert_eq!(index.len(), 2);
+}
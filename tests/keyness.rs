@@ -0,0 +1,44 @@
+use std::io::Cursor;
+
+use kuroyasu_bicycle_book_wordcount::keyness::{compare, Measure};
+use kuroyasu_bicycle_book_wordcount::{count, CountOption, Frequencies};
+
+fn frequencies(text: &str) -> Frequencies {
+    Frequencies::from(count(Cursor::new(text), CountOption::Word))
+}
+
+#[test]
+fn keywords_ranked_by_each_measure() {
+    let target = frequencies("cat cat cat cat dog the the the the the");
+    let reference = frequencies("dog dog dog dog cat the the the the the bird");
+    for measure in [
+        Measure::LogLikelihood,
+        Measure::ChiSquare,
+        Measure::LogRatio,
+    ] {
+        let keywords = compare(&target, &reference, measure);
+        let keys: Vec<_> = keywords.iter().map(|k| k.key).collect();
+        assert_eq!(keys.len(), 4);
+        assert_eq!(keys[0], "cat", "{:?}", measure);
+        assert_eq!(keys[3], "dog", "{:?}", measure);
+        // 相対頻度がほぼ同じ`the`は、特徴的でないトークンに分類される。
+        assert!(keywords
+            .iter()
+            .all(|k| k.key != "the" || k.score(measure).abs() < 0.5));
+    }
+
+    let keywords = compare(&target, &reference, Measure::LogLikelihood);
+    let bird = keywords.iter().find(|k| k.key == "bird").unwrap();
+    assert_eq!((bird.target, bird.reference), (0, 1));
+    assert!(bird.log_likelihood < 0.0 && bird.chi_square < 0.0 && bird.log_ratio < 0.0);
+}
+
+#[test]
+fn identical_corpora_are_not_key() {
+    let freqs = frequencies("a b b c c c");
+    for k in compare(&freqs, &freqs, Measure::LogLikelihood) {
+        assert_eq!(k.log_likelihood, 0.0);
+        assert_eq!(k.chi_square, 0.0);
+        assert_eq!(k.log_ratio, 0.0);
+    }
+}
@@ -1,5 +1,6 @@
 use std::io::Cursor;
 
+use kuroyasu_bicycle_book_wordcount::tokenizer::LineOption;
 use kuroyasu_bicycle_book_wordcount::{count, CountOption};
 
 #[macro_use]
@@ -31,4 +32,36 @@ fn linecount_lfcr() {
         "bb" => 2,
         "cc" => 1
     });
-}
\ No newline at end of file
+}
+
+#[test]
+fn linecount_trim_and_collapse_whitespace() {
+    let input = Cursor::new("foo  bar\n\tfoo bar \n\n \u{3000}\nfoo\u{3000}bar");
+    let option = LineOption {
+        trim: true,
+        collapse_whitespace: true,
+        skip_blank: true,
+        ..Default::default()
+    };
+    let freqs = count(input, CountOption::LineWith(option));
+    assert_eq!(freqs.len(), 1);
+    assert_map!(freqs, {
+        "foo bar" => 3
+    });
+}
+
+#[test]
+fn linecount_ignore_case_keeps_first_form() {
+    let input = Cursor::new("Tokyo\nTOKYO\ntokyo\nKyoto\n\n");
+    let option = LineOption {
+        ignore_case: true,
+        ..Default::default()
+    };
+    let freqs = count(input, CountOption::LineWith(option));
+    assert_eq!(freqs.len(), 3);
+    assert_map!(freqs, {
+        "Tokyo" => 3,
+        "Kyoto" => 1,
+        "" => 1
+    });
+}
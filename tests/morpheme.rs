@@ -0,0 +1,37 @@
+use std::io::Cursor;
+
+use kuroyasu_bicycle_book_wordcount::morph::{MorphemeOption, PartOfSpeech, PosSet};
+use kuroyasu_bicycle_book_wordcount::{count, CountOption};
+
+#[macro_use]
+mod utils;
+
+#[test]
+fn morphemecount_works() {
+    let input = Cursor::new("東京都に住んでいる\n京都に住む");
+    let freqs = count(input, CountOption::Morpheme(Default::default()));
+    assert_map!(freqs, {
+        "東京" => 1,
+        "京都" => 1,
+        "都" => 1,
+        "に" => 2,
+        "住ん" => 1,
+        "住む" => 1
+    });
+}
+
+#[test]
+fn morphemecount_base_form_of_verbs() {
+    let input = Cursor::new("東京都に住んでいる\n京都に住む");
+    let option = MorphemeOption {
+        base_form: true,
+        with_pos: true,
+        pos: PosSet::empty().with(PartOfSpeech::Verb),
+    };
+    let freqs = count(input, CountOption::Morpheme(option));
+    assert_eq!(freqs.len(), 2);
+    assert_map!(freqs, {
+        "住む/動詞" => 2,
+        "いる/動詞" => 1
+    });
+}
@@ -0,0 +1,75 @@
+use std::io::Cursor;
+
+use kuroyasu_bicycle_book_wordcount::{count, CountOption};
+
+#[macro_use]
+mod utils;
+
+#[test]
+fn charbigram_works() {
+    let input = Cursor::new("東京都\n京都");
+    let freqs = count(
+        input,
+        CountOption::CharNgram {
+            n: 2,
+            cross_lines: false,
+        },
+    );
+    assert_eq!(freqs.len(), 2);
+    assert_map!(freqs, {
+        "東京" => 1,
+        "京都" => 2
+    });
+}
+
+#[test]
+fn charbigram_cross_lines() {
+    let input = Cursor::new("東京都\n京都");
+    let freqs = count(
+        input,
+        CountOption::CharNgram {
+            n: 2,
+            cross_lines: true,
+        },
+    );
+    assert_eq!(freqs.len(), 3);
+    assert_map!(freqs, {
+        "東京" => 1,
+        "京都" => 2,
+        "都京" => 1
+    });
+}
+
+#[test]
+fn wordbigram_works() {
+    let input = Cursor::new("machine learning and\nmachine learning");
+    let freqs = count(
+        input,
+        CountOption::WordNgram {
+            n: 2,
+            cross_lines: false,
+        },
+    );
+    assert_eq!(freqs.len(), 2);
+    assert_map!(freqs, {
+        "machine learning" => 2,
+        "learning and" => 1
+    });
+}
+
+#[test]
+fn wordtrigram_cross_lines() {
+    let input = Cursor::new("a b\nc d");
+    let freqs = count(
+        input,
+        CountOption::WordNgram {
+            n: 3,
+            cross_lines: true,
+        },
+    );
+    assert_eq!(freqs.len(), 2);
+    assert_map!(freqs, {
+        "a b c" => 1,
+        "b c d" => 1
+    });
+}
@@ -0,0 +1,68 @@
+use std::io::Cursor;
+
+use kuroyasu_bicycle_book_wordcount::output::{Format, Table, Value};
+use kuroyasu_bicycle_book_wordcount::{count, CountOption, Frequencies};
+
+fn written(table: &Table, format: Format) -> String {
+    let mut buffer = Vec::new();
+    table.write(format, &mut buffer).unwrap();
+    String::from_utf8(buffer).unwrap()
+}
+
+#[test]
+fn frequencies_in_stable_order() {
+    let freqs = Frequencies::from(count(Cursor::new("b a c a b d"), CountOption::Word));
+    let table = Table::from(&freqs);
+    assert_eq!(
+        written(&table, Format::Json),
+        "[\n  {\"key\":\"a\",\"count\":2},\n  {\"key\":\"b\",\"count\":2},\n  \
+         {\"key\":\"c\",\"count\":1},\n  {\"key\":\"d\",\"count\":1}\n]\n"
+    );
+    assert_eq!(written(&Table::new(["key"]), Format::Json), "[]\n");
+}
+
+#[test]
+fn escaped_keys() {
+    let mut table = Table::new(["key", "score"]);
+    table.push(vec!["tab\there".into(), 0.5.into()]);
+    table.push(vec!["line\nbreak\\".into(), Value::Null]);
+    assert_eq!(
+        written(&table, Format::Tsv),
+        "key\tscore\ntab\\there\t0.5\nline\\nbreak\\\\\t\n"
+    );
+    assert_eq!(
+        written(&table, Format::Csv),
+        "key,score\ntab\there,0.5\n\"line\nbreak\\\",\n"
+    );
+    assert_eq!(
+        written(&table, Format::Ndjson),
+        "{\"key\":\"tab\\there\",\"score\":0.5}\n{\"key\":\"line\\nbreak\\\\\",\"score\":null}\n"
+    );
+}
+
+#[test]
+fn aligned_table() {
+    let mut table = Table::new(["key", "count", "weight"]);
+    table.push(vec!["日本語".into(), 12.into(), 0.25.into()]);
+    table.push(vec!["a".into(), 3.into(), 1.0.into()]);
+    assert_eq!(
+        written(&table, Format::Table),
+        "key     count    weight\n\
+         日本語     12  0.250000\n\
+         a           3  1.000000\n"
+    );
+}
+
+#[test]
+fn format_names() {
+    for format in [
+        Format::Json,
+        Format::Ndjson,
+        Format::Csv,
+        Format::Tsv,
+        Format::Table,
+    ] {
+        assert_eq!(format.name().parse::<Format>(), Ok(format));
+    }
+    assert!("xml".parse::<Format>().is_err());
+}
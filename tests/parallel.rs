@@ -0,0 +1,77 @@
+use std::fs;
+use std::io::Cursor;
+use std::path::PathBuf;
+
+use kuroyasu_bicycle_book_wordcount::parallel::{count_file, effective_jobs, try_count_file};
+use kuroyasu_bicycle_book_wordcount::tokenizer::LineOption;
+use kuroyasu_bicycle_book_wordcount::{count, try_count, CountOption};
+
+/// テストごとに異なる一時ファイルに`contents`を書き込む。
+fn temp_file(name: &str, contents: &[u8]) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("wordcount-{}-{}", std::process::id(), name));
+    fs::write(&path, contents).unwrap();
+    path
+}
+
+#[test]
+fn parallel_count_matches_sequential_count() {
+    let text: String = (0..500)
+        .map(|i| format!("line {} 東京 {}\r\n", i % 7, "x".repeat(i % 13)))
+        .collect::<String>()
+        + "last line";
+    let path = temp_file("matches", text.as_bytes());
+    for option in [
+        CountOption::Word,
+        CountOption::Line,
+        CountOption::CharNgram {
+            n: 3,
+            cross_lines: true,
+        },
+    ] {
+        let expected = count(Cursor::new(&text), option);
+        for jobs in [1, 2, 3, 8, 64] {
+            assert_eq!(count_file(&path, option, jobs), expected, "jobs = {}", jobs);
+        }
+    }
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn parallel_count_reports_absolute_position() {
+    let mut contents = "aa bb\n".repeat(100).into_bytes();
+    contents.extend_from_slice(b"cc \xff\n");
+    contents.extend_from_slice("dd\n".repeat(100).as_bytes());
+    let path = temp_file("position", &contents);
+    let expected = try_count(Cursor::new(&contents), CountOption::Word).unwrap_err();
+    let e = try_count_file(&path, CountOption::Word, 4).unwrap_err();
+    assert_eq!((e.line(), e.offset()), (101, 603));
+    assert_eq!((e.line(), e.offset()), (expected.line(), expected.offset()));
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn ignore_case_lines_use_one_job() {
+    let option = CountOption::LineWith(LineOption {
+        ignore_case: true,
+        ..LineOption::default()
+    });
+    assert_eq!(effective_jobs(option, 4), 1);
+    assert_eq!(effective_jobs(CountOption::Line, 4), 4);
+}
+
+#[test]
+fn parallel_count_skips_bom() {
+    let text = format!("\u{feff}{}", "hello\nworld\n".repeat(100));
+    let path = temp_file("bom", text.as_bytes());
+    let expected = count(Cursor::new(&text), CountOption::Line);
+    assert_eq!(expected["hello"], 100);
+    for jobs in [1, 4] {
+        assert_eq!(
+            count_file(&path, CountOption::Line, jobs),
+            expected,
+            "jobs = {}",
+            jobs
+        );
+    }
+    fs::remove_file(path).unwrap();
+}
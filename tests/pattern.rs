@@ -0,0 +1,51 @@
+use std::io::Cursor;
+
+use kuroyasu_bicycle_book_wordcount::{count_pattern, Pattern, PatternError};
+
+#[macro_use]
+mod utils;
+
+#[test]
+fn patterncount_group() {
+    let input = Cursor::new(
+        r#"2021-01-01 ERROR E001 disk full
+2021-01-01 INFO started
+2021-01-02 ERROR E002 timeout
+2021-01-03 ERROR  E001 disk full
+"#,
+    );
+    let pattern = Pattern::new(r"ERROR\s+(\w+)").unwrap().group(1).unwrap();
+    let freqs = count_pattern(input, &pattern);
+    assert_eq!(freqs.len(), 2);
+    assert_map!(freqs, {
+        "E001" => 2,
+        "E002" => 1
+    });
+}
+
+#[test]
+fn patterncount_named_group() {
+    let input = Cursor::new("user=alice user=bob user=alice");
+    let pattern = Pattern::new(r"user=(?P<name>\w+)")
+        .unwrap()
+        .named_group("name")
+        .unwrap();
+    let freqs = count_pattern(input, &pattern);
+    assert_map!(freqs, {
+        "alice" => 2,
+        "bob" => 1
+    });
+}
+
+#[test]
+fn pattern_errors() {
+    assert!(matches!(Pattern::new(r"(\w+"), Err(PatternError::Regex(_))));
+    assert!(matches!(
+        Pattern::new(r"(\w+)").unwrap().group(2),
+        Err(PatternError::NoSuchGroup(_))
+    ));
+    assert!(matches!(
+        Pattern::new(r"(\w+)").unwrap().named_group("name"),
+        Err(PatternError::NoSuchGroup(_))
+    ));
+}
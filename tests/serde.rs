@@ -0,0 +1,116 @@
+#![cfg(feature = "serde")]
+
+use std::io::Cursor;
+
+use kuroyasu_bicycle_book_wordcount::collocation::{Association, Collocations};
+use kuroyasu_bicycle_book_wordcount::concordance::{find, Context, Occurrence};
+use kuroyasu_bicycle_book_wordcount::index::InvertedIndex;
+use kuroyasu_bicycle_book_wordcount::keyness::{compare, Measure};
+use kuroyasu_bicycle_book_wordcount::output::Format;
+use kuroyasu_bicycle_book_wordcount::stats::Statistics;
+use kuroyasu_bicycle_book_wordcount::tfidf::Corpus;
+use kuroyasu_bicycle_book_wordcount::tokenizer::WordTokenizer;
+use kuroyasu_bicycle_book_wordcount::topk::TopK;
+use kuroyasu_bicycle_book_wordcount::{count, CountOption, Frequencies};
+
+fn frequencies(text: &str) -> Frequencies {
+    Frequencies::from(count(Cursor::new(text), CountOption::Word))
+}
+
+#[test]
+fn frequencies_as_map() {
+    let freqs = frequencies("a b a");
+    let json = serde_json::to_string(&freqs).unwrap();
+    let map: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(map, serde_json::json!({"a": 2, "b": 1}));
+    let decoded: Frequencies = serde_json::from_str(&json).unwrap();
+    assert_eq!(decoded, freqs);
+    assert_eq!(decoded.total(), 3);
+}
+
+#[test]
+fn statistics_round_trip() {
+    let stats = Statistics::new(&frequencies("a b a c"));
+    let json = serde_json::to_string(&stats).unwrap();
+    assert_eq!(serde_json::from_str::<Statistics>(&json).unwrap(), stats);
+}
+
+#[test]
+fn borrowed_results_as_objects() {
+    let target = frequencies("rust rust code");
+    let reference = frequencies("code code rust");
+    let keywords = compare(&target, &reference, Measure::LogLikelihood);
+    let json = serde_json::to_value(&keywords).unwrap();
+    assert_eq!(json[0]["key"], "rust");
+    assert_eq!(json[0]["target"], 2);
+
+    let mut corpus = Corpus::new();
+    corpus.add("a", target.clone());
+    corpus.add("b", reference.clone());
+    let weights = corpus.tf_idf(0);
+    let json = serde_json::to_value(&weights).unwrap();
+    assert_eq!(json.as_array().unwrap().len(), weights.len());
+    assert_eq!(json[0]["term"], weights[0].term);
+
+    let mut collocations = Collocations::new();
+    collocations.add("new", "york");
+    collocations.add("new", "york");
+    let ranked = collocations.rank(Association::Pmi, 1);
+    let json = serde_json::to_value(&ranked).unwrap();
+    assert_eq!(json[0]["first"], "new");
+    assert_eq!(json[0]["second"], "york");
+    assert_eq!(json[0]["count"], 2);
+}
+
+#[test]
+fn borrowed_results_with_escaped_keys() {
+    let mut top = TopK::new(3, 1 << 20);
+    for key in ["a\tb", "say \"hi\"", "a\nb", "a\tb"] {
+        top.add(key);
+    }
+    let json = serde_json::to_string(&top.top()).unwrap();
+    assert!(json.contains(r#""key":"a\tb""#), "{}", json);
+    let decoded: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(
+        decoded,
+        serde_json::json!([
+            {"key": "a\tb", "count": 2, "error": 0},
+            {"key": "a\nb", "count": 1, "error": 0},
+            {"key": "say \"hi\"", "count": 1, "error": 0},
+        ])
+    );
+}
+
+#[test]
+fn positions_round_trip() {
+    let occurrences = find(
+        Cursor::new("a key here"),
+        WordTokenizer,
+        "key",
+        Context::Tokens(1),
+    );
+    let json = serde_json::to_string(&occurrences).unwrap();
+    assert_eq!(
+        serde_json::from_str::<Vec<Occurrence>>(&json).unwrap(),
+        occurrences
+    );
+
+    let mut index = InvertedIndex::new();
+    index
+        .add("a.txt", Cursor::new("a b\na"), WordTokenizer)
+        .unwrap();
+    let json = serde_json::to_string(&index).unwrap();
+    assert_eq!(serde_json::from_str::<InvertedIndex>(&json).unwrap(), index);
+}
+
+#[test]
+fn format_names() {
+    assert_eq!(
+        serde_json::to_string(&Format::Ndjson).unwrap(),
+        "\"ndjson\""
+    );
+    assert_eq!(
+        serde_json::from_str::<Format>("\"csv\"").unwrap(),
+        Format::Csv
+    );
+}
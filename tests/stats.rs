@@ -0,0 +1,26 @@
+use std::io::Cursor;
+
+use kuroyasu_bicycle_book_wordcount::stats::Statistics;
+use kuroyasu_bicycle_book_wordcount::{count, CountOption, Frequencies};
+
+#[test]
+fn statistics_of_any_count_option() {
+    let text = "aab\nab\n";
+    let chars = Statistics::new(&Frequencies::from(count(
+        Cursor::new(text),
+        CountOption::Char,
+    )));
+    assert_eq!((chars.tokens, chars.types), (5, 2));
+    assert_eq!((chars.hapax_legomena, chars.dis_legomena), (0, 1));
+    assert!(chars.entropy > 0.97 && chars.entropy < 0.98);
+
+    let lines = Statistics::new(&Frequencies::from(count(
+        Cursor::new(text),
+        CountOption::Line,
+    )));
+    assert_eq!(lines.hapax_legomena, 2);
+    assert_eq!(lines.type_token_ratio, 1.0);
+    assert_eq!(lines.simpsons_d, 0.0);
+    assert_eq!(lines.entropy, 1.0);
+    assert_eq!(lines.zipf_exponent, Some(0.0));
+}
@@ -0,0 +1,29 @@
+use std::io::Cursor;
+
+use kuroyasu_bicycle_book_wordcount::tokenizer::WordTokenizer;
+use kuroyasu_bicycle_book_wordcount::{count, count_stems, CountOption};
+
+#[macro_use]
+mod utils;
+
+#[test]
+fn stemcount_works() {
+    let input = Cursor::new("He runs, she ran.\nRunning is fun; the runners were running.");
+    let freqs = count(input, CountOption::Stem);
+    assert_map!(freqs, {
+        "run" => 4,
+        "runner" => 1,
+        "be" => 2,
+        "fun" => 1
+    });
+}
+
+#[test]
+fn most_frequent_surface_of_stems() {
+    let input = Cursor::new("connect connected\nconnection connected connecting");
+    let stems = count_stems(input, WordTokenizer);
+    assert_eq!(stems.len(), 1);
+    assert_eq!(stems["connect"].count(), 5);
+    assert_eq!(stems["connect"].surfaces()["connected"], 2);
+    assert_eq!(stems["connect"].most_frequent_surface(), "connected");
+}
@@ -0,0 +1,26 @@
+use std::io::Cursor;
+
+use kuroyasu_bicycle_book_wordcount::stopwords::Stopwords;
+use kuroyasu_bicycle_book_wordcount::tokenizer::StopwordTokenizer;
+use kuroyasu_bicycle_book_wordcount::{count_with, CountOption};
+
+#[macro_use]
+mod utils;
+
+#[test]
+fn stopwords_are_not_counted() {
+    let input = Cursor::new("東京都に住んでいる\n京都に住む");
+    let tokenizer = CountOption::Morpheme(Default::default()).tokenizer();
+    let freqs = count_with(
+        input,
+        StopwordTokenizer::new(tokenizer, Stopwords::japanese()),
+    );
+    assert_eq!(freqs.len(), 5);
+    assert_map!(freqs, {
+        "東京" => 1,
+        "京都" => 1,
+        "都" => 1,
+        "住ん" => 1,
+        "住む" => 1
+    });
+}
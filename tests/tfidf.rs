@@ -0,0 +1,41 @@
+use std::io::Cursor;
+
+use kuroyasu_bicycle_book_wordcount::tfidf::{Bm25, Corpus};
+use kuroyasu_bicycle_book_wordcount::{count, CountOption, Frequencies};
+
+fn corpus(texts: &[&str]) -> Corpus {
+    let mut corpus = Corpus::new();
+    for (i, text) in texts.iter().enumerate() {
+        let freqs = count(Cursor::new(text), CountOption::Word);
+        corpus.add(format!("{}.txt", i), Frequencies::from(freqs));
+    }
+    corpus
+}
+
+#[test]
+fn characteristic_keywords_per_document() {
+    let corpus = corpus(&[
+        "rust borrow checker\nrust traits",
+        "python generators\npython decorators",
+        "rust and python",
+    ]);
+    assert_eq!(corpus.len(), 3);
+    assert_eq!(corpus.documents()[1].name(), "1.txt");
+    assert_eq!(corpus.documents()[1].frequencies()["python"], 2);
+    assert_eq!(corpus.document_frequency("rust"), 2);
+    assert_eq!(corpus.document_frequency("go"), 0);
+
+    let tf_idf = corpus.tf_idf(0);
+    assert_eq!(tf_idf.len(), 4);
+    assert_eq!(
+        tf_idf.iter().map(|w| w.term).collect::<Vec<_>>(),
+        ["borrow", "checker", "traits", "rust"]
+    );
+    assert!(tf_idf.windows(2).all(|w| w[0].weight >= w[1].weight));
+
+    for i in 0..corpus.len() {
+        let bm25 = corpus.bm25(i, Bm25::default());
+        assert!(bm25.iter().all(|w| w.weight > 0.0));
+    }
+    assert_eq!(corpus.bm25(2, Bm25::default())[0].term, "and");
+}
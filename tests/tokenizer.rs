@@ -0,0 +1,47 @@
+use std::io::Cursor;
+
+use kuroyasu_bicycle_book_wordcount::count_with;
+use kuroyasu_bicycle_book_wordcount::tokenizer::Tokenizer;
+
+#[macro_use]
+mod utils;
+
+/// 空行で区切られた段落を1つのトークンとするトークナイザー
+#[derive(Default)]
+struct Paragraph {
+    lines: Vec<String>,
+}
+
+impl Paragraph {
+    fn flush(&mut self, emit: &mut dyn FnMut(&str)) {
+        if !self.lines.is_empty() {
+            emit(&self.lines.join(" "));
+            self.lines.clear();
+        }
+    }
+}
+
+impl Tokenizer for Paragraph {
+    fn tokenize(&mut self, line: &str, emit: &mut dyn FnMut(&str)) {
+        if line.is_empty() {
+            self.flush(emit);
+        } else {
+            self.lines.push(line.to_string());
+        }
+    }
+
+    fn finish(&mut self, emit: &mut dyn FnMut(&str)) {
+        self.flush(emit);
+    }
+}
+
+#[test]
+fn custom_tokenizer_with_state() {
+    let input = Cursor::new("a\nb\n\nc\n\na\nb");
+    let freqs = count_with(input, Paragraph::default());
+    assert_eq!(freqs.len(), 2);
+    assert_map!(freqs, {
+        "a b" => 2,
+        "c" => 1
+    });
+}
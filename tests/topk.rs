@@ -0,0 +1,26 @@
+use std::io::Cursor;
+
+use kuroyasu_bicycle_book_wordcount::{count, count_top, CountOption};
+
+#[test]
+fn approximate_top_lines_with_small_memory() {
+    let text: String = (0..2000)
+        .map(|i| match i % 4 {
+            0 => "GET /\n".to_string(),
+            1 => "GET /about\n".to_string(),
+            _ => format!("request {}\n", i),
+        })
+        .collect();
+    let exact = count(Cursor::new(&text), CountOption::Line);
+    let top = count_top(Cursor::new(&text), CountOption::Line.tokenizer(), 2, 4096);
+    assert!(top.len() < exact.len());
+    assert_eq!(top.total(), 2000);
+
+    let hitters = top.top();
+    assert_eq!(hitters.len(), 2);
+    assert_eq!(hitters[0].key, "GET /");
+    assert_eq!(hitters[1].key, "GET /about");
+    for h in hitters {
+        assert!(h.guaranteed() <= exact[h.key] && exact[h.key] <= h.count);
+    }
+}